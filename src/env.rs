@@ -8,12 +8,15 @@ pub struct Environment {
     pub database_url: String,
     pub database_name: String,
     pub fdc_key: String,
+    /// Optional `FDC_URL` value, defaulting to [`crate::fdc::FDC_URL`].
+    pub fdc_url: String,
 }
 
 struct PartialEnvironment {
     database_url: Option<String>,
     database_name: Option<String>,
     fdc_key: Option<String>,
+    fdc_url: Option<String>,
 }
 
 pub fn get() -> Result<Environment> {
@@ -22,6 +25,7 @@ pub fn get() -> Result<Environment> {
         database_url: None,
         database_name: None,
         fdc_key: None,
+        fdc_url: None,
     };
     let penv = std::env::vars().fold(penv, |penv, (key, value)| {
         if key == "DATABASE_URL" {
@@ -39,21 +43,34 @@ pub fn get() -> Result<Environment> {
                 fdc_key: Some(value),
                 ..penv
             }
+        } else if key == "FDC_URL" {
+            PartialEnvironment {
+                fdc_url: Some(value),
+                ..penv
+            }
         } else {
             penv
         }
     });
-    if penv.database_url.is_none() {
-        Err(anyhow!("Environment needs DATABASE_URL value"))
-    } else if penv.database_name.is_none() {
-        Err(anyhow!("Environment needs DATABASE_NAME value"))
-    } else if penv.fdc_key.is_none() {
-        Err(anyhow!("Environment needs FDC_KEY value"))
-    } else {
-        Ok(Environment {
-            database_url: penv.database_url.unwrap(),
-            database_name: penv.database_name.unwrap(),
-            fdc_key: penv.fdc_key.unwrap(),
-        })
+    match penv {
+        PartialEnvironment {
+            database_url: None, ..
+        } => Err(anyhow!("Environment needs DATABASE_URL value")),
+        PartialEnvironment {
+            database_name: None,
+            ..
+        } => Err(anyhow!("Environment needs DATABASE_NAME value")),
+        PartialEnvironment { fdc_key: None, .. } => Err(anyhow!("Environment needs FDC_KEY value")),
+        PartialEnvironment {
+            database_url: Some(database_url),
+            database_name: Some(database_name),
+            fdc_key: Some(fdc_key),
+            fdc_url,
+        } => Ok(Environment {
+            database_url,
+            database_name,
+            fdc_key,
+            fdc_url: fdc_url.unwrap_or_else(|| crate::fdc::FDC_URL.into()),
+        }),
     }
 }
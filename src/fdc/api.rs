@@ -1,8 +1,11 @@
-//! Contains all of the json payloads we get from the FDC API.
+//! Contains all of the json payloads we get from the FDC API. We serialize them in the same camel
+//! case FDC uses, so that our own payloads read back like theirs.
+
+use super::DataType;
 
 /// Corresponds to the base information every food has.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AbridgedFoodItem {
     pub fdc_id: i32,
     pub data_type: String,
@@ -10,69 +13,358 @@ pub struct AbridgedFoodItem {
     pub food_nutrients: Vec<AbridgedFoodNutrient>,
 }
 
-/// Corresponds to a nutrient.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+/// Corresponds to a single page of search results.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub total_hits: i32,
+    pub current_page: i32,
+    pub total_pages: i32,
+    pub foods: Vec<AbridgedFoodItem>,
+}
+
+/// Corresponds to a nutrient. Search results identify nutrients by id, while the abridged
+/// format of "v1/food" and "v1/foods/list" identifies them by number and calls the value an
+/// amount.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AbridgedFoodNutrient {
-    pub nutrient_id: i32,
+    #[serde(default)]
+    pub nutrient_id: Option<i32>,
+    #[serde(default, alias = "number")]
+    pub nutrient_number: Option<String>,
+    #[serde(alias = "name")]
     pub nutrient_name: String,
-    pub unit_name: String,
+    pub unit_name: NutrientUnit,
+    #[serde(alias = "amount")]
     pub value: f32,
 }
 
-/// Corresponds to the metadata that only branded foods have.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+/// Corresponds to the metadata that only branded foods have. Manufacturers leave out much of
+/// it, so even the ingredients and the serving size are optional.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BrandedFoodItem {
     pub fdc_id: i32,
+    pub description: String,
     pub brand_owner: Option<String>,
     pub brand_name: Option<String>,
     pub gtin_upc: Option<String>,
     pub household_serving_full_text: Option<String>,
-    pub ingredients: String,
-    pub serving_size: f32,
-    pub serving_size_unit: String,
+    pub ingredients: Option<String>,
+    pub serving_size: Option<f32>,
+    pub serving_size_unit: Option<String>,
     pub label_nutrients: Option<LabelNutrients>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
 }
 
-/// Corresponds to label nutrients on branded foods.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+/// Corresponds to label nutrients on branded foods, per serving. Labels only list the nutrients
+/// the manufacturer reported, so every nutrient is optional.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LabelNutrients {
-    pub fat: LabelNutrient,
-    pub saturated_fat: LabelNutrient,
-    pub trans_fat: LabelNutrient,
-    pub cholesterol: LabelNutrient,
-    pub sodium: LabelNutrient,
-    pub carbohydrates: LabelNutrient,
-    pub fiber: LabelNutrient,
-    pub sugars: LabelNutrient,
-    pub protein: LabelNutrient,
-    pub calcium: LabelNutrient,
-    pub iron: LabelNutrient,
-    pub potassium: LabelNutrient,
-    pub calories: LabelNutrient,
+    pub calories: Option<LabelNutrient>,
+    pub fat: Option<LabelNutrient>,
+    pub saturated_fat: Option<LabelNutrient>,
+    pub trans_fat: Option<LabelNutrient>,
+    pub polyunsaturated_fat: Option<LabelNutrient>,
+    pub monounsaturated_fat: Option<LabelNutrient>,
+    pub cholesterol: Option<LabelNutrient>,
+    pub sodium: Option<LabelNutrient>,
+    pub carbohydrates: Option<LabelNutrient>,
+    pub fiber: Option<LabelNutrient>,
+    pub soluble_fiber: Option<LabelNutrient>,
+    pub insoluble_fiber: Option<LabelNutrient>,
+    pub sugars: Option<LabelNutrient>,
+    #[serde(alias = "addedSugars")]
+    pub added_sugar: Option<LabelNutrient>,
+    pub sugar_alcohol: Option<LabelNutrient>,
+    pub protein: Option<LabelNutrient>,
+    pub vitamin_d: Option<LabelNutrient>,
+    pub calcium: Option<LabelNutrient>,
+    pub iron: Option<LabelNutrient>,
+    pub potassium: Option<LabelNutrient>,
+    pub vitamin_a: Option<LabelNutrient>,
+    pub vitamin_c: Option<LabelNutrient>,
 }
 
 /// Corresponds to a single nutrient's data in a branded food.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LabelNutrient {
     pub value: f32,
 }
 
-/// Corresponds to the metadata of collections of both `FoodAttribute` and `FoodPortion` structs.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
-pub struct APFoodItem {
+/// Corresponds to the metadata of foundation foods, which FDC derives from the samples in
+/// `input_foods`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoundationFoodItem {
+    pub fdc_id: i32,
+    pub description: String,
+    pub food_class: Option<String>,
+    pub foot_note: Option<String>,
+    pub is_historical_reference: Option<bool>,
+    pub ndb_number: Option<i32>,
+    pub publication_date: Option<String>,
+    pub scientific_name: Option<String>,
+    pub food_category: Option<FoodCategory>,
+    #[serde(default)]
+    pub food_attributes: Vec<FoodAttribute>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
+    #[serde(default)]
+    pub food_components: Vec<FoodComponent>,
+    #[serde(default)]
+    pub food_portions: Vec<FoodPortion>,
+    #[serde(default)]
+    pub input_foods: Vec<InputFoodFoundation>,
+    #[serde(default)]
+    pub nutrient_conversion_factors: Vec<NutrientConversionFactor>,
+}
+
+/// Corresponds to the metadata of foods from the final release of the Standard Reference.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SRLegacyFoodItem {
+    pub fdc_id: i32,
+    pub description: String,
+    pub food_class: Option<String>,
+    pub is_historical_reference: Option<bool>,
+    pub ndb_number: Option<i32>,
+    pub publication_date: Option<String>,
+    pub scientific_name: Option<String>,
+    pub food_category: Option<FoodCategory>,
+    #[serde(default)]
+    pub food_attributes: Vec<FoodAttribute>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
+    #[serde(default)]
+    pub food_portions: Vec<FoodPortion>,
+    #[serde(default)]
+    pub nutrient_conversion_factors: Vec<NutrientConversionFactor>,
+}
+
+/// Corresponds to the metadata of foods from the Food and Nutrient Database for Dietary Studies,
+/// which are recipes of the ingredients in `input_foods`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SurveyFoodItem {
+    pub fdc_id: i32,
+    pub description: String,
+    pub food_class: Option<String>,
+    pub food_code: Option<String>,
+    pub publication_date: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub wweia_food_category: Option<WweiaFoodCategory>,
+    #[serde(default)]
+    pub food_attributes: Vec<FoodAttribute>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
+    #[serde(default)]
+    pub food_portions: Vec<FoodPortion>,
+    #[serde(default)]
+    pub input_foods: Vec<InputFoodSurvey>,
+}
+
+/// Corresponds to the metadata of experimental foods.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentalFoodItem {
     pub fdc_id: i32,
+    pub description: String,
+    pub food_class: Option<String>,
+    pub publication_date: Option<String>,
+    #[serde(default)]
     pub food_attributes: Vec<FoodAttribute>,
+    #[serde(default)]
     pub food_portions: Vec<FoodPortion>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FoodNutrient>,
+}
+
+/// Corresponds to the category of a foundation or sr legacy food.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodCategory {
+    pub id: Option<i32>,
+    pub code: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Corresponds to the "What We Eat in America" category of a survey food.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WweiaFoodCategory {
+    pub wweia_food_category_code: i32,
+    pub wweia_food_category_description: String,
+}
+
+/// Corresponds to a part of a foundation food, like the bones of a cut of meat.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodComponent {
+    pub id: i32,
+    pub name: Option<String>,
+    pub data_points: Option<i32>,
+    pub gram_weight: Option<f32>,
+    pub is_refuse: Option<bool>,
+    pub min_year_acquired: Option<i32>,
+    pub percent_weight: Option<f32>,
+}
+
+/// Corresponds to a sample a foundation food was derived from.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputFoodFoundation {
+    pub id: i32,
+    pub food_description: Option<String>,
+    pub input_food: Option<SampleFoodItem>,
+}
+
+/// Corresponds to the metadata of a sample food.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleFoodItem {
+    pub fdc_id: i32,
+    pub data_type: Option<String>,
+    pub description: String,
+    pub food_class: Option<String>,
+    pub publication_date: Option<String>,
+}
+
+/// Corresponds to an ingredient of a survey food.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputFoodSurvey {
+    pub id: i32,
+    pub amount: Option<f32>,
+    pub food_description: Option<String>,
+    pub ingredient_code: Option<i32>,
+    pub ingredient_description: Option<String>,
+    pub ingredient_weight: Option<f32>,
+    pub portion_code: Option<String>,
+    pub portion_description: Option<String>,
+    pub sequence_number: Option<i32>,
+    pub survey_flag: Option<i32>,
+    pub unit: Option<String>,
+}
+
+/// Corresponds to the factors FDC used to derive protein and energy from the measured nutrients.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum NutrientConversionFactor {
+    #[serde(rename = ".ProteinConversionFactor")]
+    Protein { value: f32 },
+    #[serde(rename = ".CalorieConversionFactor", rename_all = "camelCase")]
+    Calorie {
+        protein_value: Option<f32>,
+        fat_value: Option<f32>,
+        carbohydrate_value: Option<f32>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Corresponds to the amount of a nutrient in 100 g of a food. Foods derived from analyses carry
+/// the statistics of those analyses, along with how the amount was derived. Sparse payloads may
+/// leave out the id and even the nutrient, whose amounts are then not reported.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodNutrient {
+    pub id: Option<i32>,
+    pub amount: Option<f32>,
+    pub data_points: Option<i32>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub median: Option<f32>,
+    pub nutrient: Option<Nutrient>,
+    pub food_nutrient_derivation: Option<FoodNutrientDerivation>,
+}
+
+/// Corresponds to a nutrient, which FDC identifies both by id and by its legacy `number`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Nutrient {
+    pub id: i32,
+    pub number: Option<String>,
+    pub name: String,
+    pub rank: Option<i32>,
+    pub unit_name: NutrientUnit,
+}
+
+/// The units FDC reports nutrients in. FDC writes them in any case, like `"MG"` or `"mg"`, and
+/// uses a few units we do not interpret, like `"SP_GR"` for specific gravity.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum NutrientUnit {
+    Gram,
+    Milligram,
+    Microgram,
+    Kilocalorie,
+    Kilojoule,
+    InternationalUnit,
+    Other(String),
+}
+
+impl From<String> for NutrientUnit {
+    fn from(unit_name: String) -> NutrientUnit {
+        match &unit_name.to_lowercase()[..] {
+            "g" => NutrientUnit::Gram,
+            "mg" => NutrientUnit::Milligram,
+            "ug" | "µg" | "mcg" => NutrientUnit::Microgram,
+            "kcal" => NutrientUnit::Kilocalorie,
+            "kj" => NutrientUnit::Kilojoule,
+            "iu" => NutrientUnit::InternationalUnit,
+            _ => NutrientUnit::Other(unit_name),
+        }
+    }
+}
+
+impl From<NutrientUnit> for String {
+    fn from(unit: NutrientUnit) -> String {
+        match unit {
+            NutrientUnit::Gram => "G".into(),
+            NutrientUnit::Milligram => "MG".into(),
+            NutrientUnit::Microgram => "UG".into(),
+            NutrientUnit::Kilocalorie => "KCAL".into(),
+            NutrientUnit::Kilojoule => "kJ".into(),
+            NutrientUnit::InternationalUnit => "IU".into(),
+            NutrientUnit::Other(unit_name) => unit_name,
+        }
+    }
+}
+
+impl From<&str> for NutrientUnit {
+    fn from(unit_name: &str) -> NutrientUnit {
+        NutrientUnit::from(unit_name.to_string())
+    }
+}
+
+/// Corresponds to the procedure through which the amount of a nutrient was derived.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodNutrientDerivation {
+    pub id: i32,
+    pub code: String,
+    pub description: String,
+    pub food_nutrient_source: Option<FoodNutrientSource>,
+}
+
+/// Corresponds to the source of the amount of a nutrient, like an analysis or a calculation.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodNutrientSource {
+    pub id: i32,
+    pub code: String,
+    pub description: String,
 }
 
 /// Corresponds to the food attributes,
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoodAttribute {
     pub id: i32,
     pub sequence_number: Option<i32>,
@@ -81,8 +373,8 @@ pub struct FoodAttribute {
 }
 
 /// Corresponds to metadata of a food attribute.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoodAttributeType {
     pub id: i32,
     pub name: String,
@@ -90,8 +382,8 @@ pub struct FoodAttributeType {
 }
 
 /// Corresponds to the portions of a given food.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all(deserialize = "camelCase", serialize = "snake_case"))]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoodPortion {
     pub id: i32,
     pub amount: Option<f32>,
@@ -100,12 +392,85 @@ pub struct FoodPortion {
     pub modifier: Option<String>,
     pub portion_description: Option<String>,
     pub sequence_number: Option<i32>,
+    pub measure_unit: Option<MeasureUnit>,
 }
 
-/// A helper for parsing whether or not a food falls into the branded category.
-#[derive(Debug, Deserialize)]
+/// Corresponds to the unit of a portion. FDC uses the unit "undetermined" for portions which are
+/// described through their modifier instead.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeasureUnit {
+    pub id: i32,
+    pub name: String,
+    pub abbreviation: Option<String>,
+}
+
+/// The full details of a food, by its `dataType`.
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "dataType")]
 pub enum FDCMeta {
     Branded(BrandedFoodItem),
-    Other(APFoodItem),
+    Foundation(FoundationFoodItem),
+    #[serde(rename = "SR Legacy")]
+    SRLegacy(SRLegacyFoodItem),
+    #[serde(rename = "Survey (FNDDS)")]
+    Survey(SurveyFoodItem),
+    Experimental(ExperimentalFoodItem),
+}
+
+impl FDCMeta {
+    /// the type of the food
+    pub fn data_type(&self) -> DataType {
+        match self {
+            FDCMeta::Branded(_) => DataType::Branded,
+            FDCMeta::Foundation(_) => DataType::Foundation,
+            FDCMeta::SRLegacy(_) => DataType::SRLegacy,
+            FDCMeta::Survey(_) => DataType::Survey,
+            FDCMeta::Experimental(_) => DataType::Experimental,
+        }
+    }
+
+    /// the id of the food
+    pub fn fdc_id(&self) -> i32 {
+        match self {
+            FDCMeta::Branded(meta) => meta.fdc_id,
+            FDCMeta::Foundation(meta) => meta.fdc_id,
+            FDCMeta::SRLegacy(meta) => meta.fdc_id,
+            FDCMeta::Survey(meta) => meta.fdc_id,
+            FDCMeta::Experimental(meta) => meta.fdc_id,
+        }
+    }
+
+    /// the description of the food
+    pub fn description(&self) -> &str {
+        match self {
+            FDCMeta::Branded(meta) => &meta.description,
+            FDCMeta::Foundation(meta) => &meta.description,
+            FDCMeta::SRLegacy(meta) => &meta.description,
+            FDCMeta::Survey(meta) => &meta.description,
+            FDCMeta::Experimental(meta) => &meta.description,
+        }
+    }
+
+    /// the nutrients in 100 g of the food
+    pub fn food_nutrients(&self) -> &[FoodNutrient] {
+        match self {
+            FDCMeta::Branded(meta) => &meta.food_nutrients,
+            FDCMeta::Foundation(meta) => &meta.food_nutrients,
+            FDCMeta::SRLegacy(meta) => &meta.food_nutrients,
+            FDCMeta::Survey(meta) => &meta.food_nutrients,
+            FDCMeta::Experimental(meta) => &meta.food_nutrients,
+        }
+    }
+
+    /// the portions of the food, which branded foods do not have
+    pub fn food_portions(&self) -> &[FoodPortion] {
+        match self {
+            FDCMeta::Branded(_) => &[],
+            FDCMeta::Foundation(meta) => &meta.food_portions,
+            FDCMeta::SRLegacy(meta) => &meta.food_portions,
+            FDCMeta::Survey(meta) => &meta.food_portions,
+            FDCMeta::Experimental(meta) => &meta.food_portions,
+        }
+    }
 }
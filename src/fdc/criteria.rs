@@ -0,0 +1,239 @@
+//! Contains the [`FoodSearchCriteria`] and [`FoodListCriteria`] request bodies we send to
+//! "v1/foods/search" and "v1/foods/list", along with the options they share.
+
+use chrono::NaiveDate;
+
+/// The most results FDC returns in a single page.
+pub const MAX_PAGE_SIZE: i32 = 200;
+
+/// The types of food that FDC publishes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataType {
+    Branded,
+    Foundation,
+    #[serde(rename = "Survey (FNDDS)")]
+    Survey,
+    #[serde(rename = "SR Legacy")]
+    SRLegacy,
+    Experimental,
+}
+
+/// The fields FDC is able to sort search results by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum SortBy {
+    #[serde(rename = "dataType.keyword")]
+    DataType,
+    #[serde(rename = "lowercaseDescription.keyword")]
+    Description,
+    #[serde(rename = "fdcId")]
+    FdcId,
+    #[serde(rename = "publishedDate")]
+    PublishedDate,
+}
+
+/// The direction of a sort.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// The level of detail FDC returns foods with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Abridged,
+    Full,
+}
+
+impl Format {
+    /// the value of the `format` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Abridged => "abridged",
+            Format::Full => "full",
+        }
+    }
+}
+
+/// Corresponds to the body of a search request. Only the query is required; every other field
+/// is left out of the request unless it is set through one of the builder methods.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodSearchCriteria {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<Vec<DataType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<SortBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<SortOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand_owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_all_words: Option<bool>,
+}
+
+impl FoodSearchCriteria {
+    /// generate criteria which only match on the query
+    pub fn new<S: Into<String>>(query: S) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            query: query.into(),
+            data_type: None,
+            page_size: None,
+            page_number: None,
+            sort_by: None,
+            sort_order: None,
+            brand_owner: None,
+            start_date: None,
+            end_date: None,
+            require_all_words: None,
+        }
+    }
+
+    /// only match foods of the given types
+    pub fn data_types(self, data_types: &[DataType]) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            data_type: Some(data_types.to_vec()),
+            ..self
+        }
+    }
+
+    /// the number of results per page, kept between 1 and [`MAX_PAGE_SIZE`] as FDC does
+    pub fn page_size(self, page_size: i32) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            page_size: Some(page_size.clamp(1, MAX_PAGE_SIZE)),
+            ..self
+        }
+    }
+
+    /// the page of results to request, counting from 1
+    pub fn page_number(self, page_number: i32) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            page_number: Some(page_number),
+            ..self
+        }
+    }
+
+    /// sort the results by a field in the given direction
+    pub fn sort(self, sort_by: SortBy, sort_order: SortOrder) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            sort_by: Some(sort_by),
+            sort_order: Some(sort_order),
+            ..self
+        }
+    }
+
+    /// only match branded foods of the given brand owner
+    pub fn brand_owner<S: Into<String>>(self, brand_owner: S) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            brand_owner: Some(brand_owner.into()),
+            ..self
+        }
+    }
+
+    /// only match foods published on or after the date
+    pub fn start_date(self, start_date: NaiveDate) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            start_date: Some(start_date),
+            ..self
+        }
+    }
+
+    /// only match foods published on or before the date
+    pub fn end_date(self, end_date: NaiveDate) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            end_date: Some(end_date),
+            ..self
+        }
+    }
+
+    /// whether every word of the query must appear in a result
+    pub fn require_all_words(self, require_all_words: bool) -> FoodSearchCriteria {
+        FoodSearchCriteria {
+            require_all_words: Some(require_all_words),
+            ..self
+        }
+    }
+}
+
+impl From<&str> for FoodSearchCriteria {
+    fn from(query: &str) -> FoodSearchCriteria {
+        FoodSearchCriteria::new(query)
+    }
+}
+
+impl From<String> for FoodSearchCriteria {
+    fn from(query: String) -> FoodSearchCriteria {
+        FoodSearchCriteria::new(query)
+    }
+}
+
+/// Corresponds to the body of a list request. Every field is left out of the request unless it
+/// is set through one of the builder methods.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodListCriteria {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<Vec<DataType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<SortBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<SortOrder>,
+}
+
+impl FoodListCriteria {
+    /// the page size FDC uses when none is given
+    pub const DEFAULT_PAGE_SIZE: i32 = 50;
+
+    /// generate criteria which list every food
+    pub fn new() -> FoodListCriteria {
+        FoodListCriteria::default()
+    }
+
+    /// only list foods of the given types
+    pub fn data_types(self, data_types: &[DataType]) -> FoodListCriteria {
+        FoodListCriteria {
+            data_type: Some(data_types.to_vec()),
+            ..self
+        }
+    }
+
+    /// the number of foods per page, kept between 1 and [`MAX_PAGE_SIZE`] as FDC does
+    pub fn page_size(self, page_size: i32) -> FoodListCriteria {
+        FoodListCriteria {
+            page_size: Some(page_size.clamp(1, MAX_PAGE_SIZE)),
+            ..self
+        }
+    }
+
+    /// the page of foods to request, counting from 1
+    pub fn page_number(self, page_number: i32) -> FoodListCriteria {
+        FoodListCriteria {
+            page_number: Some(page_number),
+            ..self
+        }
+    }
+
+    /// sort the foods by a field in the given direction
+    pub fn sort(self, sort_by: SortBy, sort_order: SortOrder) -> FoodListCriteria {
+        FoodListCriteria {
+            sort_by: Some(sort_by),
+            sort_order: Some(sort_order),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Contains the [`FdcError`] type every [`FDCService`](super::FDCService) request fails with.
+
+use std::sync::Arc;
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+
+/// The ways a request to the FDC API can fail.
+#[derive(Debug, thiserror::Error)]
+pub enum FdcError {
+    /// The api key is missing, invalid or disabled.
+    #[error("FDC rejected the api key ({status}): {body}")]
+    InvalidApiKey { status: StatusCode, body: ErrorBody },
+    /// The api key has used up its hourly budget of requests.
+    #[error("FDC rate limit exceeded ({status}): {body}")]
+    RateLimited { status: StatusCode, body: ErrorBody },
+    /// The api key used up its budget, as an earlier response reported, so the request was not
+    /// sent.
+    #[error("the FDC rate limit of the api key is used up for the hour")]
+    Exhausted,
+    /// The endpoint or food does not exist.
+    #[error("FDC could not find the resource ({status}): {body}")]
+    NotFound { status: StatusCode, body: ErrorBody },
+    /// Any other unsuccessful status.
+    #[error("FDC responded with {status}: {body}")]
+    Status { status: StatusCode, body: ErrorBody },
+    /// A food of a bulk request was not in the response.
+    #[error("FDC did not return food {fdc_id}")]
+    Missing { fdc_id: i32 },
+    /// The batch of a bulk request holding the food failed, for the reason shared by its foods.
+    #[error("the request for food {fdc_id} failed: {source}")]
+    Batch { fdc_id: i32, source: Arc<FdcError> },
+    /// The request could not be sent or the response could not be read.
+    #[error("could not reach FDC: {0}")]
+    Network(#[from] reqwest::Error),
+    /// The response was not the payload we expected.
+    #[error("could not deserialize the FDC response at `{path}`: {source}")]
+    Deserialize {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+impl FdcError {
+    /// the error matching an unsuccessful status and its response body
+    pub fn from_response(status: StatusCode, text: &str) -> FdcError {
+        let body = ErrorBody::parse(text);
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                FdcError::InvalidApiKey { status, body }
+            }
+            StatusCode::TOO_MANY_REQUESTS => FdcError::RateLimited { status, body },
+            StatusCode::NOT_FOUND => FdcError::NotFound { status, body },
+            _ => FdcError::Status { status, body },
+        }
+    }
+
+    /// the HTTP status of the response, if there was one
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            FdcError::InvalidApiKey { status, .. }
+            | FdcError::RateLimited { status, .. }
+            | FdcError::NotFound { status, .. }
+            | FdcError::Status { status, .. } => Some(*status),
+            FdcError::Network(e) => e.status(),
+            FdcError::Batch { source, .. } => source.status(),
+            FdcError::Exhausted | FdcError::Missing { .. } | FdcError::Deserialize { .. } => None,
+        }
+    }
+
+    /// the error body FDC responded with, if there was one
+    pub fn body(&self) -> Option<&ErrorBody> {
+        match self {
+            FdcError::InvalidApiKey { body, .. }
+            | FdcError::RateLimited { body, .. }
+            | FdcError::NotFound { body, .. }
+            | FdcError::Status { body, .. } => Some(body),
+            FdcError::Batch { source, .. } => source.body(),
+            _ => None,
+        }
+    }
+}
+
+/// Corresponds to the body of an unsuccessful response. The api gateway in front of FDC
+/// responds with `{"error": {"code": ..., "message": ...}}`, but anything else is kept as text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorBody {
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub text: String,
+}
+
+impl ErrorBody {
+    /// parse the body of an unsuccessful response
+    pub fn parse(text: &str) -> ErrorBody {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            error: Inner,
+        }
+        #[derive(Deserialize)]
+        struct Inner {
+            code: Option<String>,
+            message: Option<String>,
+        }
+        let (code, message) = match serde_json::from_str::<Wrapper>(text) {
+            Ok(Wrapper { error }) => (error.code, error.message),
+            Err(_) => (None, None),
+        };
+        ErrorBody {
+            code,
+            message,
+            text: text.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.code, &self.message) {
+            (Some(code), Some(message)) => write!(f, "{} ({})", message, code),
+            (None, Some(message)) => write!(f, "{}", message),
+            (Some(code), None) => write!(f, "{}", code),
+            (None, None) => write!(f, "{:?}", self.text),
+        }
+    }
+}
+
+/// Deserialize a response body, keeping track of the json path of any failure.
+pub fn deserialize<T: DeserializeOwned>(text: &str) -> Result<T, FdcError> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|e| FdcError::Deserialize {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })
+}
+
+/// Deserialize an already parsed json value, keeping track of the json path of any failure.
+pub fn deserialize_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T, FdcError> {
+    serde_path_to_error::deserialize(value).map_err(|e| FdcError::Deserialize {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })
+}
@@ -0,0 +1,112 @@
+//! The api.data.gov gateway in front of FDC allows each key 1,000 requests per hour and reports
+//! what is left of that budget on every response. This module provides the [`RateLimit`] we
+//! track from those headers and the [`RetryPolicy`] deciding how failed requests are retried.
+
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+/// The request budget of an api key, as last reported by FDC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    /// when FDC last reported the remaining budget
+    pub reported_at: Option<Instant>,
+}
+
+impl RateLimit {
+    /// The window the budget of a key is counted over.
+    pub const WINDOW: Duration = Duration::from_secs(60 * 60);
+
+    /// Whether FDC reported the budget as used up within the last window. The gateway does not
+    /// say when the budget resets, so we only hold requests back for a window after the report.
+    pub fn is_exhausted(&self, now: Instant) -> bool {
+        match (self.remaining, self.reported_at) {
+            (Some(0), Some(reported_at)) => {
+                now.saturating_duration_since(reported_at) < Self::WINDOW
+            }
+            _ => false,
+        }
+    }
+
+    /// update the budget from the `X-RateLimit-*` headers of a response, if it has them
+    pub fn update(&mut self, headers: &HeaderMap) {
+        let parse = |key: &str| {
+            headers
+                .get(key)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u32>().ok())
+        };
+        if let Some(limit) = parse("x-ratelimit-limit") {
+            self.limit = Some(limit);
+        }
+        if let Some(remaining) = parse("x-ratelimit-remaining") {
+            self.remaining = Some(remaining);
+            self.reported_at = Some(Instant::now());
+        }
+    }
+}
+
+/// How requests which fail with a 429 or 5xx status are retried. The delay before each retry
+/// grows exponentially from `base_delay` and is jittered uniformly below that bound, unless the
+/// response asked for a specific delay through its `Retry-After` header. A request is never put
+/// to sleep for longer than `max_delay`; responses asking for more than that fail immediately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// a policy which never retries
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// whether a response with this status is worth retrying
+    pub fn is_retryable(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    /// The delay before retry number `attempt` (counting from 0) of a request whose response
+    /// had the given headers, or [`None`] if the request should not be retried.
+    pub fn delay(&self, attempt: u32, headers: &HeaderMap) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        let retry_after = headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        match retry_after {
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => {
+                let bound = self
+                    .base_delay
+                    .checked_mul(2u32.saturating_pow(attempt))
+                    .unwrap_or(self.max_delay)
+                    .min(self.max_delay);
+                Some(bound.mul_f64(rand::thread_rng().gen_range(0.0..=1.0)))
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! A local stand-in for the FDC API so that [`FDCService`](super::FDCService) can be tested
+//! without a network connection or an api key. The [`MockServer`] answers each request with
+//! responses mounted by the test, most of which are the canned payloads in [`fixtures`].
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A canned HTTP response.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    /// a response with a json body
+    pub fn json<S: Into<String>>(status: u16, body: S) -> MockResponse {
+        MockResponse {
+            status,
+            headers: vec![("Content-Type".into(), "application/json".into())],
+            body: body.into(),
+        }
+    }
+
+    /// attach a header to the response
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> MockResponse {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// A request the server has received.
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockRequest {
+    /// the body of the request as json
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+
+    /// the value of a query parameter
+    pub fn query_param(&self, key: &str) -> Option<&str> {
+        self.query.split('&').find_map(|pair| {
+            let mut split = pair.splitn(2, '=');
+            match (split.next(), split.next()) {
+                (Some(k), Some(v)) if k == key => Some(v),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// A route answers requests with its queued responses, repeating the last one forever.
+struct Route {
+    method: String,
+    path: String,
+    responses: VecDeque<MockResponse>,
+}
+
+#[derive(Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<MockRequest>,
+}
+
+/// An HTTP server listening on a random local port.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    /// bind the server and start serving on the current runtime
+    pub async fn start() -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, server_state.clone()));
+            }
+        });
+        MockServer { addr, state }
+    }
+
+    /// the url to hand to [`FDCService::with_base_url`](super::FDCService::with_base_url)
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// answer requests to `method path` with the responses, in order
+    pub fn mount<I>(&self, method: &str, path: &str, responses: I)
+    where
+        I: IntoIterator<Item = MockResponse>,
+    {
+        self.state.lock().unwrap().routes.push(Route {
+            method: method.into(),
+            path: path.into(),
+            responses: responses.into_iter().collect(),
+        });
+    }
+
+    /// every request received so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+/// Read a single request off the stream, record it, and write the matching response.
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let request = match read_request(&mut stream).await {
+        Some(request) => request,
+        None => return,
+    };
+    let response = {
+        let mut state = state.lock().unwrap();
+        let response = state
+            .routes
+            .iter_mut()
+            .find(|route| route.method == request.method && route.path == request.path)
+            .and_then(|route| {
+                if route.responses.len() > 1 {
+                    route.responses.pop_front()
+                } else {
+                    route.responses.front().cloned()
+                }
+            })
+            .unwrap_or_else(|| MockResponse::json(404, fixtures::NOT_FOUND));
+        state.requests.push(request);
+        response
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    response.headers.iter().for_each(|(key, value)| {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    });
+    head.push_str("\r\n");
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(response.body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Parse the request line, headers and `Content-Length` body of an HTTP/1.1 request.
+async fn read_request(stream: &mut TcpStream) -> Option<MockRequest> {
+    let mut buffer = Vec::with_capacity(4096);
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = match target.find('?') {
+        Some(i) => (target[..i].to_string(), target[i + 1..].to_string()),
+        None => (target.to_string(), String::new()),
+    };
+    let headers = lines
+        .filter_map(|line| {
+            let i = line.find(':')?;
+            Some((
+                line[..i].trim().to_string(),
+                line[i + 1..].trim().to_string(),
+            ))
+        })
+        .collect::<Vec<_>>();
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = buffer.split_off(head_end + 4);
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Some(MockRequest {
+        method,
+        path,
+        query,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// Canned FDC payloads in the shape the live API returns, trimmed down to a few nutrients each.
+pub mod fixtures {
+    /// `POST v1/foods/search` for the upc `"00027000690260"`
+    pub const FOODS_SEARCH_UPC: &str = include_str!("fixtures/foods_search_upc.json");
+    /// `POST v1/foods/search` for the phrase `"Cheddar Cheese"`
+    pub const FOODS_SEARCH_CHEDDAR: &str = include_str!("fixtures/foods_search_cheddar.json");
+    /// `POST v1/foods/search` for `"apple"`, split over three pages of two foods
+    pub const FOODS_SEARCH_APPLE: [&str; 3] = [
+        include_str!("fixtures/foods_search_apple_1.json"),
+        include_str!("fixtures/foods_search_apple_2.json"),
+        include_str!("fixtures/foods_search_apple_3.json"),
+    ];
+    /// `POST v1/foods` for a branded, sr legacy, survey and foundation food, in that order
+    pub const FOODS: &str = include_str!("fixtures/foods.json");
+    /// `GET v1/food/173323?format=abridged&nutrients=203,204`
+    pub const FOOD_ABRIDGED: &str = include_str!("fixtures/food_abridged.json");
+    /// `POST v1/foods/list` for sr legacy foods, split over two pages of at most two foods
+    pub const FOODS_LIST: [&str; 2] = [
+        include_str!("fixtures/foods_list_1.json"),
+        include_str!("fixtures/foods_list_2.json"),
+    ];
+    /// the body FDC answers with for an unknown api key
+    pub const API_KEY_INVALID: &str = include_str!("fixtures/api_key_invalid.json");
+    /// the body FDC answers with once the hourly rate limit is used up
+    pub const OVER_RATE_LIMIT: &str = include_str!("fixtures/over_rate_limit.json");
+    /// the body FDC answers with for unknown routes
+    pub const NOT_FOUND: &str = include_str!("fixtures/not_found.json");
+
+    /// the foods of [`FOODS`] as the json FDC responded with
+    pub fn foods_json() -> Vec<serde_json::Value> {
+        serde_json::from_str(FOODS).unwrap()
+    }
+
+    /// the foods of [`FOODS`], deserialized
+    pub fn foods() -> Vec<crate::fdc::FDCMeta> {
+        foods_json()
+            .into_iter()
+            .map(|food| crate::fdc::meta_from_value(food).unwrap())
+            .collect()
+    }
+}
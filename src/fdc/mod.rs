@@ -2,80 +2,379 @@
 //! [FoodData Central](https://fdc.nal.usda.gov/index.html) API though the [`FDCService`] struct.
 
 pub mod api;
+pub mod criteria;
+pub mod error;
+pub mod limit;
 
 pub use api::*;
+pub use criteria::*;
+pub use error::FdcError;
+pub use limit::{RateLimit, RetryPolicy};
 
-use anyhow::Result;
-use reqwest::Client;
+use crate::env::Environment;
 
-/// `FDCService` implements the http requests to the FDC API through an Actix client.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::{Client, RequestBuilder};
+
+type Result<T> = std::result::Result<T, FdcError>;
+
+/// The base url of the live FDC API.
+pub const FDC_URL: &str = "https://api.nal.usda.gov/fdc";
+
+/// The most ids FDC accepts in a single "v1/foods" request.
+pub const MAX_FOODS_PER_REQUEST: usize = 20;
+
+/// The foods of a bulk request, each paired with the id it was requested by.
+pub type FoodResults = Vec<(i32, Result<FDCMeta>)>;
+
+/// The foods of a bulk request as the json FDC responded with, each paired with the id it was
+/// requested by.
+pub type FoodJsonResults = Vec<(i32, Result<serde_json::Value>)>;
+
+/// `FDCService` implements the http requests to the FDC API through an Actix client. Clones
+/// share the tracked [`RateLimit`], as they share the api key.
 #[derive(Clone, Debug)]
 pub struct FDCService {
     pub fdc_key: String,
+    pub base_url: String,
+    pub retry_policy: RetryPolicy,
+    rate_limit: Arc<Mutex<RateLimit>>,
 }
 
 impl FDCService {
-    /// generate a new FDCService
+    /// generate a new FDCService which targets the live FDC API
     pub fn new<S: Into<String>>(fdc_key: S) -> FDCService {
         FDCService {
             fdc_key: fdc_key.into(),
+            base_url: FDC_URL.into(),
+            retry_policy: RetryPolicy::default(),
+            rate_limit: Arc::new(Mutex::new(RateLimit::default())),
+        }
+    }
+
+    /// generate a new FDCService from the key and url of the environment
+    pub fn from_env(environment: &Environment) -> FDCService {
+        FDCService::new(&environment.fdc_key[..]).with_base_url(&environment.fdc_url[..])
+    }
+
+    /// point the service at a different base url, like that of a local mock server
+    pub fn with_base_url<S: Into<String>>(self, base_url: S) -> FDCService {
+        FDCService {
+            base_url: base_url.into().trim_end_matches('/').into(),
+            ..self
+        }
+    }
+
+    /// change how failed requests are retried
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> FDCService {
+        FDCService {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// the request budget of the api key, as reported by the last response
+    pub fn rate_limit(&self) -> RateLimit {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Send a request and read the response body, recording the budget the response reports.
+    /// Requests are not sent while the budget is used up. Requests failing with a retryable
+    /// status are retried according to the [`RetryPolicy`]; other unsuccessful statuses are
+    /// turned into the matching [`FdcError`].
+    async fn send(&self, request: RequestBuilder) -> Result<String> {
+        if self.rate_limit().is_exhausted(Instant::now()) {
+            return Err(FdcError::Exhausted);
+        }
+        let mut attempt = 0;
+        loop {
+            let res = request
+                .try_clone()
+                .expect("request bodies are never streamed")
+                .send()
+                .await?;
+            self.rate_limit.lock().unwrap().update(res.headers());
+            let status = res.status();
+            let delay = if RetryPolicy::is_retryable(status) {
+                self.retry_policy.delay(attempt, res.headers())
+            } else {
+                None
+            };
+            let text = res.text().await?;
+            match delay {
+                _ if status.is_success() => return Ok(text),
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(FdcError::from_response(status, &text)),
+            }
         }
     }
 
-    /// Make a request to "v1/foods/search" and collect the first 10 results to a vector.
-    pub async fn v1_foods_search<S: Into<String>>(
+    /// the url of an endpoint like `"v1/foods"`, with the api key attached
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}?api_key={}", self.base_url, endpoint, self.fdc_key)
+    }
+
+    /// Make a request to "v1/foods/search" and collect the first page of results to a vector.
+    /// Unless the criteria says otherwise, this is the first 10 results.
+    pub async fn v1_foods_search<C: Into<FoodSearchCriteria>>(
         &self,
         client: &Client,
-        query: S,
+        criteria: C,
     ) -> Result<Vec<AbridgedFoodItem>> {
+        let criteria = criteria.into();
+        let criteria = FoodSearchCriteria {
+            page_size: criteria.page_size.or(Some(10)),
+            ..criteria
+        };
+        Ok(self.v1_foods_search_page(client, criteria).await?.foods)
+    }
+
+    /// Make a request to "v1/foods/search" for the single page of results the criteria asks for.
+    pub async fn v1_foods_search_page<C: Into<FoodSearchCriteria>>(
+        &self,
+        client: &Client,
+        criteria: C,
+    ) -> Result<SearchResult> {
         // make the request
-        let body = serde_json::json!({ "query": query.into(), "pageSize": 10 });
-        let mut res = client
-            .post(format!(
-                "https://api.nal.usda.gov/fdc/v1/foods/search?api_key={}",
-                self.fdc_key
-            ))
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
+        let text = self
+            .send(
+                client
+                    .post(self.url("v1/foods/search"))
+                    .json(&criteria.into()),
+            )
             .await?;
+        error::deserialize(&text)
+    }
 
-        // extract "foods" json array and deserialize
-        Ok(serde_json::from_value(res["foods"].take())?)
+    /// Stream the pages of a "v1/foods/search" request, starting from the page of the criteria
+    /// and requesting the next page only once the previous one has been consumed.
+    pub fn v1_foods_search_pages<'a, C: Into<FoodSearchCriteria>>(
+        &'a self,
+        client: &'a Client,
+        criteria: C,
+    ) -> impl Stream<Item = Result<SearchResult>> + 'a {
+        let criteria = criteria.into();
+        let first = criteria.page_number.unwrap_or(1);
+        stream::try_unfold(Some(first), move |page_number| {
+            let criteria = criteria.clone();
+            async move {
+                let criteria = match page_number {
+                    Some(page_number) => criteria.page_number(page_number),
+                    None => return Ok(None),
+                };
+                let page = self.v1_foods_search_page(client, criteria).await?;
+                let next = if page.current_page < page.total_pages && !page.foods.is_empty() {
+                    Some(page.current_page + 1)
+                } else {
+                    None
+                };
+                Ok(Some((page, next)))
+            }
+        })
     }
 
-    /// Make a request to "v1/foods"
-    pub async fn v1_foods(&self, client: &Client, fdc_ids: &[i32]) -> Result<Vec<FDCMeta>> {
-        // make the request
+    /// Stream every food matching a "v1/foods/search" request, walking the pages transparently.
+    pub fn v1_foods_search_all<'a, C: Into<FoodSearchCriteria>>(
+        &'a self,
+        client: &'a Client,
+        criteria: C,
+    ) -> impl Stream<Item = Result<AbridgedFoodItem>> + 'a {
+        self.v1_foods_search_pages(client, criteria)
+            .map_ok(|page| stream::iter(page.foods.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Make requests to "v1/foods", in batches of at most [`MAX_FOODS_PER_REQUEST`] ids. Each
+    /// requested id is paired with its food, or with the reason it could not be had, so that a
+    /// single malformed or unknown food, or a single failed batch, does not fail the others.
+    pub async fn v1_foods(&self, client: &Client, fdc_ids: &[i32]) -> FoodResults {
+        self.v1_foods_json(client, fdc_ids)
+            .await
+            .into_iter()
+            .map(|(fdc_id, food)| (fdc_id, food.and_then(meta_from_value)))
+            .collect()
+    }
+
+    /// Make requests to "v1/foods" like [`FDCService::v1_foods`], keeping each food as the json
+    /// FDC responded with.
+    pub async fn v1_foods_json(&self, client: &Client, fdc_ids: &[i32]) -> FoodJsonResults {
+        let mut results = Vec::with_capacity(fdc_ids.len());
+        for chunk in fdc_ids.chunks(MAX_FOODS_PER_REQUEST) {
+            match self.v1_foods_batch(client, chunk).await {
+                Ok(foods) => results.extend(pair_foods(chunk, foods)),
+                Err(e) => {
+                    let source = Arc::new(e);
+                    results.extend(chunk.iter().map(|&fdc_id| {
+                        let source = source.clone();
+                        (fdc_id, Err(FdcError::Batch { fdc_id, source }))
+                    }));
+                }
+            }
+        }
+        results
+    }
+
+    /// helper function which makes a single "v1/foods" request for a batch of ids
+    async fn v1_foods_batch(
+        &self,
+        client: &Client,
+        fdc_ids: &[i32],
+    ) -> Result<Vec<serde_json::Value>> {
         let body = serde_json::json!({ "fdcIds": fdc_ids, "format": "full" });
-        let mut res = client
-            .post(format!(
-                "https://api.nal.usda.gov/fdc/v1/foods?api_key={}",
-                self.fdc_key
-            ))
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
+        let text = self
+            .send(client.post(self.url("v1/foods")).json(&body))
+            .await?;
+        error::deserialize(&text)
+    }
+
+    /// Make a request to "v1/food/{fdc_id}" for the full details of a single food. If
+    /// `nutrients` is nonempty, only the nutrients with those numbers (at most 25) are returned.
+    pub async fn v1_food(
+        &self,
+        client: &Client,
+        fdc_id: i32,
+        nutrients: &[i32],
+    ) -> Result<FDCMeta> {
+        let res = self
+            .v1_food_request(client, fdc_id, Format::Full, nutrients)
             .await?;
+        meta_from_value(res)
+    }
 
-        // map the values associated to the `dataType` key so that they can match the enum variants
-        res.as_array_mut().map(|foods| {
-            foods
-                .iter_mut()
-                .for_each(|food| match food["dataType"].as_str() {
-                    Some("Branded") => {}
-                    _ => {
-                        food["dataType"] = serde_json::Value::String("Other".into());
-                    }
-                })
-        });
-
-        // deserialize
-        Ok(serde_json::from_value(res)?)
+    /// Make a request to "v1/food/{fdc_id}" for the abridged details of a single food. If
+    /// `nutrients` is nonempty, only the nutrients with those numbers (at most 25) are returned.
+    pub async fn v1_food_abridged(
+        &self,
+        client: &Client,
+        fdc_id: i32,
+        nutrients: &[i32],
+    ) -> Result<AbridgedFoodItem> {
+        let res = self
+            .v1_food_request(client, fdc_id, Format::Abridged, nutrients)
+            .await?;
+        error::deserialize_value(res)
     }
+
+    /// helper function which makes the "v1/food/{fdc_id}" request in either format
+    async fn v1_food_request(
+        &self,
+        client: &Client,
+        fdc_id: i32,
+        format: Format,
+        nutrients: &[i32],
+    ) -> Result<serde_json::Value> {
+        let mut query = vec![("format", format.as_str().to_string())];
+        if !nutrients.is_empty() {
+            let numbers = nutrients.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+            query.push(("nutrients", numbers.join(",")));
+        }
+        let text = self
+            .send(
+                client
+                    .get(self.url(&format!("v1/food/{}", fdc_id)))
+                    .query(&query),
+            )
+            .await?;
+        error::deserialize(&text)
+    }
+
+    /// Make a request to "v1/foods/list" for the single page of foods the criteria asks for.
+    pub async fn v1_foods_list(
+        &self,
+        client: &Client,
+        criteria: &FoodListCriteria,
+    ) -> Result<Vec<AbridgedFoodItem>> {
+        let text = self
+            .send(client.post(self.url("v1/foods/list")).json(criteria))
+            .await?;
+        error::deserialize(&text)
+    }
+
+    /// Stream the pages of "v1/foods/list", starting from the page of the criteria. The list
+    /// endpoint does not report how many pages there are, so the stream ends at the first page
+    /// holding fewer foods than the page size, which is kept between 1 and [`MAX_PAGE_SIZE`] so
+    /// that an empty page always ends it.
+    pub fn v1_foods_list_pages<'a>(
+        &'a self,
+        client: &'a Client,
+        criteria: FoodListCriteria,
+    ) -> impl Stream<Item = Result<Vec<AbridgedFoodItem>>> + 'a {
+        let first = criteria.page_number.unwrap_or(1);
+        // the page size may have been set on the field, around the clamp of the builder
+        let page_size = criteria
+            .page_size
+            .unwrap_or(FoodListCriteria::DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let criteria = criteria.page_size(page_size);
+        stream::try_unfold(Some(first), move |page_number| {
+            let criteria = criteria.clone();
+            async move {
+                let criteria = match page_number {
+                    Some(page_number) => criteria.page_number(page_number),
+                    None => return Ok(None),
+                };
+                let foods = self.v1_foods_list(client, &criteria).await?;
+                let next = if foods.len() < page_size as usize {
+                    None
+                } else {
+                    criteria.page_number.map(|n| n + 1)
+                };
+                Ok(Some((foods, next)))
+            }
+        })
+    }
+
+    /// Stream every food of "v1/foods/list", walking the pages transparently.
+    pub fn v1_foods_list_all<'a>(
+        &'a self,
+        client: &'a Client,
+        criteria: FoodListCriteria,
+    ) -> impl Stream<Item = Result<AbridgedFoodItem>> + 'a {
+        self.v1_foods_list_pages(client, criteria)
+            .map_ok(|foods| stream::iter(foods.into_iter().map(Ok)))
+            .try_flatten()
+    }
+}
+
+/// Deserialize the json of a food in the full format into the [`FDCMeta`] variant of its
+/// `dataType`. The variant is picked before deserializing, so that errors keep the path to the
+/// offending field.
+pub fn meta_from_value(food: serde_json::Value) -> Result<FDCMeta> {
+    match food["dataType"].as_str() {
+        Some("Branded") => error::deserialize_value(food).map(FDCMeta::Branded),
+        Some("Foundation") => error::deserialize_value(food).map(FDCMeta::Foundation),
+        Some("SR Legacy") => error::deserialize_value(food).map(FDCMeta::SRLegacy),
+        Some("Survey (FNDDS)") => error::deserialize_value(food).map(FDCMeta::Survey),
+        Some("Experimental") => error::deserialize_value(food).map(FDCMeta::Experimental),
+        _ => error::deserialize_value(food),
+    }
+}
+
+/// helper function which pairs the requested ids of a batch with the foods FDC responded with
+fn pair_foods(fdc_ids: &[i32], foods: Vec<serde_json::Value>) -> FoodJsonResults {
+    // index the foods of the response by their id
+    let foods = foods
+        .into_iter()
+        .filter_map(|food| Some((i32::try_from(food["fdcId"].as_i64()?).ok()?, food)))
+        .collect::<HashMap<_, _>>();
+    fdc_ids
+        .iter()
+        .map(|&fdc_id| {
+            let food = foods.get(&fdc_id).cloned();
+            (fdc_id, food.ok_or(FdcError::Missing { fdc_id }))
+        })
+        .collect()
 }
 
+#[cfg(test)]
+pub mod mock;
 #[cfg(test)]
 mod test;
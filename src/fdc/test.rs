@@ -1,18 +1,29 @@
+use futures::TryStreamExt;
+
 use crate::{
     env,
-    fdc::{FDCMeta, FDCService},
+    fdc::{
+        mock::{fixtures, MockResponse, MockServer},
+        DataType, FDCMeta, FDCService, FdcError, FoodListCriteria, FoodSearchCriteria,
+        NutrientConversionFactor, NutrientUnit, RateLimit, RetryPolicy, SortBy, SortOrder,
+    },
 };
 
+use chrono::NaiveDate;
+use std::time::Duration;
+
 fn get_service() -> FDCService {
     let environment = env::get().unwrap();
-    FDCService::new(environment.fdc_key)
+    FDCService::from_env(&environment)
 }
 
-#[tokio::test]
-#[ignore]
-async fn v1_foods_search() {
-    // get the service and a client
-    let service = get_service();
+async fn get_mock_service() -> (FDCService, MockServer) {
+    let server = MockServer::start().await;
+    let service = FDCService::new("MOCK_KEY").with_base_url(server.url());
+    (service, server)
+}
+
+async fn check_v1_foods_search(service: &FDCService) {
     let client = reqwest::Client::new();
 
     // first search is a upc:
@@ -35,24 +46,51 @@ async fn v1_foods_search() {
     assert_eq!(cheese.description, "CHEDDAR CHEESE");
 }
 
-#[tokio::test]
-#[ignore]
-async fn v1_foods() {
-    // get the service and a client
-    let service = get_service();
+async fn check_v1_foods(service: &FDCService) {
     let client = reqwest::Client::new();
 
     // search one of each type of food
     let slice = [1455408, 173323, 1103005, 329370];
-    let mut results = service.v1_foods(&client, &slice).await.unwrap();
-    assert_eq!(results.len(), 4);
+    let results = service.v1_foods(&client, &slice).await;
+    assert_eq!(
+        results
+            .iter()
+            .map(|(fdc_id, _)| *fdc_id)
+            .collect::<Vec<_>>(),
+        slice.to_vec()
+    );
+    let mut results = results
+        .into_iter()
+        .map(|(_, food)| food.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results.iter().map(FDCMeta::data_type).collect::<Vec<_>>(),
+        vec![
+            DataType::Branded,
+            DataType::SRLegacy,
+            DataType::Survey,
+            DataType::Foundation
+        ]
+    );
 
     // check the foundation
     let foundation = results.pop().unwrap();
     match foundation {
-        FDCMeta::Other(meta) => {
+        FDCMeta::Foundation(meta) => {
             assert_eq!(meta.fdc_id, slice[3]);
             assert_eq!(meta.food_portions[0].id, 119685);
+            assert_eq!(
+                meta.food_portions[0]
+                    .measure_unit
+                    .as_ref()
+                    .map(|unit| &unit.name[..]),
+                Some("cup")
+            );
+            assert_eq!(
+                meta.input_foods[0].input_food.as_ref().unwrap().fdc_id,
+                329371
+            );
+            assert!(meta.food_components.is_empty());
         }
         _ => {
             panic!("Should have been a foundation food!");
@@ -62,10 +100,16 @@ async fn v1_foods() {
     // check the survey
     let survey = results.pop().unwrap();
     match survey {
-        FDCMeta::Other(meta) => {
+        FDCMeta::Survey(meta) => {
             assert_eq!(meta.fdc_id, slice[2]);
             assert_eq!(meta.food_attributes[0].id, 998724);
             assert_eq!(meta.food_portions[0].id, 239434);
+            assert_eq!(meta.input_foods[1].ingredient_weight, Some(4.7));
+            assert_eq!(
+                meta.wweia_food_category
+                    .map(|category| category.wweia_food_category_code),
+                Some(2502)
+            );
         }
         _ => {
             panic!("Should have been a survey food!");
@@ -75,10 +119,31 @@ async fn v1_foods() {
     // check the sr legacy
     let legacy = results.pop().unwrap();
     match legacy {
-        FDCMeta::Other(meta) => {
+        FDCMeta::SRLegacy(meta) => {
             assert_eq!(meta.fdc_id, slice[1]);
             assert!(meta.food_attributes.is_empty());
             assert_eq!(meta.food_portions[0].id, 92296);
+            let protein = &meta.food_nutrients[0];
+            assert_eq!(
+                protein.nutrient.as_ref().unwrap().number.as_deref(),
+                Some("203")
+            );
+            assert_eq!((protein.amount, protein.median), (Some(3.3), Some(3.3)));
+            assert_eq!(protein.data_points, Some(94));
+            let source = protein
+                .food_nutrient_derivation
+                .as_ref()
+                .and_then(|derivation| derivation.food_nutrient_source.as_ref())
+                .unwrap();
+            assert_eq!(source.description, "Analytical or derived from analytical");
+            match meta.nutrient_conversion_factors[..] {
+                [NutrientConversionFactor::Protein { value }, NutrientConversionFactor::Calorie { fat_value, .. }] =>
+                {
+                    assert_eq!(value, 6.38);
+                    assert_eq!(fat_value, Some(8.79));
+                }
+                _ => panic!("Should have had protein and calorie factors!"),
+            }
         }
         _ => {
             panic!("Should have been an sr legacy food!");
@@ -90,10 +155,692 @@ async fn v1_foods() {
     match branded {
         FDCMeta::Branded(meta) => {
             assert_eq!(meta.fdc_id, slice[0]);
-            assert_eq!(meta.label_nutrients.map(|ns| ns.fat.value), Some(13.9995));
+            assert_eq!(
+                meta.label_nutrients
+                    .and_then(|ns| ns.fat)
+                    .map(|fat| fat.value),
+                Some(13.9995)
+            );
+            assert_eq!(
+                meta.food_nutrients[1].nutrient.as_ref().unwrap().unit_name,
+                NutrientUnit::Kilocalorie
+            );
+            assert_eq!(meta.food_nutrients[1].amount, Some(857.0));
         }
         _ => {
             panic!("Should have been a branded food!");
         }
     };
 }
+
+#[tokio::test]
+#[ignore]
+async fn v1_foods_search() {
+    check_v1_foods_search(&get_service()).await;
+}
+
+#[tokio::test]
+#[ignore]
+async fn v1_foods() {
+    check_v1_foods(&get_service()).await;
+}
+
+#[tokio::test]
+async fn v1_foods_search_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount(
+        "POST",
+        "/v1/foods/search",
+        vec![
+            MockResponse::json(200, fixtures::FOODS_SEARCH_UPC),
+            MockResponse::json(200, fixtures::FOODS_SEARCH_CHEDDAR),
+        ],
+    );
+    check_v1_foods_search(&service).await;
+
+    // the service should have sent the key and the query
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].query_param("api_key"), Some("MOCK_KEY"));
+    assert_eq!(requests[0].json()["query"], "00027000690260");
+    assert_eq!(requests[1].json()["query"], "Cheddar Cheese");
+    assert_eq!(requests[1].json()["pageSize"], 10);
+}
+
+#[test]
+fn food_search_criteria() {
+    // only the query is sent by default
+    assert_eq!(
+        serde_json::to_value(FoodSearchCriteria::from("cheddar")).unwrap(),
+        serde_json::json!({ "query": "cheddar" })
+    );
+
+    // every field of the builder
+    let criteria = FoodSearchCriteria::new("cheddar")
+        .data_types(&[DataType::Foundation, DataType::SRLegacy, DataType::Survey])
+        .page_size(50)
+        .page_number(3)
+        .sort(SortBy::Description, SortOrder::Desc)
+        .brand_owner("Kraft Heinz")
+        .start_date(NaiveDate::from_ymd_opt(2019, 4, 1).unwrap())
+        .end_date(NaiveDate::from_ymd_opt(2021, 10, 28).unwrap())
+        .require_all_words(true);
+    assert_eq!(
+        serde_json::to_value(criteria).unwrap(),
+        serde_json::json!({
+            "query": "cheddar",
+            "dataType": ["Foundation", "SR Legacy", "Survey (FNDDS)"],
+            "pageSize": 50,
+            "pageNumber": 3,
+            "sortBy": "lowercaseDescription.keyword",
+            "sortOrder": "desc",
+            "brandOwner": "Kraft Heinz",
+            "startDate": "2019-04-01",
+            "endDate": "2021-10-28",
+            "requireAllWords": true,
+        })
+    );
+}
+
+#[tokio::test]
+async fn v1_foods_search_page_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount(
+        "POST",
+        "/v1/foods/search",
+        vec![MockResponse::json(200, fixtures::FOODS_SEARCH_APPLE[1])],
+    );
+    let client = reqwest::Client::new();
+    let page = service
+        .v1_foods_search_page(
+            &client,
+            FoodSearchCriteria::new("apple").page_number(2).page_size(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(page.total_hits, 5);
+    assert_eq!(page.current_page, 2);
+    assert_eq!(page.total_pages, 3);
+    assert_eq!(page.foods.len(), 2);
+    let body = server.requests().pop().unwrap().json();
+    assert_eq!(body["pageNumber"], 2);
+    assert_eq!(body["pageSize"], 2);
+}
+
+#[tokio::test]
+async fn v1_foods_search_all_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount(
+        "POST",
+        "/v1/foods/search",
+        fixtures::FOODS_SEARCH_APPLE
+            .iter()
+            .map(|page| MockResponse::json(200, *page)),
+    );
+    let client = reqwest::Client::new();
+    let foods = service
+        .v1_foods_search_all(&client, FoodSearchCriteria::new("apple").page_size(2))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        vec![171688, 1750339, 1750340, 1102644, 2117388]
+    );
+
+    // one request per page, and none after the last
+    let pages = server
+        .requests()
+        .iter()
+        .map(|request| request.json()["pageNumber"].as_i64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(pages, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn v1_foods_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(200, fixtures::FOODS)],
+    );
+    check_v1_foods(&service).await;
+
+    // the service should have asked for the full format of each id
+    let request = server.requests().pop().unwrap();
+    assert_eq!(request.json()["format"], "full");
+    assert_eq!(
+        request.json()["fdcIds"],
+        serde_json::json!([1455408, 173323, 1103005, 329370])
+    );
+}
+
+#[tokio::test]
+async fn sparse_label_nutrients() {
+    let (service, server) = get_mock_service().await;
+    let mut foods = serde_json::from_str::<serde_json::Value>(fixtures::FOODS).unwrap();
+    let label = foods[0]["labelNutrients"].as_object_mut().unwrap();
+    label.remove("transFat");
+    label.remove("potassium");
+    label.insert("addedSugar".into(), serde_json::json!({ "value": 2.0 }));
+    label.insert("vitaminD".into(), serde_json::json!({ "value": 0.5 }));
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(200, foods.to_string())],
+    );
+
+    // a label missing nutrients does not break the batch
+    let client = reqwest::Client::new();
+    let foods = service
+        .v1_foods(&client, &[1455408, 173323, 1103005, 329370])
+        .await;
+    assert_eq!(foods.len(), 4);
+    match &foods[0].1 {
+        Ok(FDCMeta::Branded(meta)) => {
+            let label = meta.label_nutrients.as_ref().unwrap();
+            assert!(label.trans_fat.is_none() && label.potassium.is_none());
+            assert_eq!(label.added_sugar.as_ref().map(|n| n.value), Some(2.0));
+            assert_eq!(label.vitamin_d.as_ref().map(|n| n.value), Some(0.5));
+        }
+        _ => panic!("Should have been a branded food!"),
+    };
+}
+
+#[tokio::test]
+async fn v1_foods_per_item_mock() {
+    let (service, server) = get_mock_service().await;
+    let mut foods = serde_json::from_str::<serde_json::Value>(fixtures::FOODS).unwrap();
+    foods[1]["foodPortions"] = serde_json::json!("not portions");
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(200, foods.to_string())],
+    );
+
+    // malformed and missing foods only fail themselves
+    let client = reqwest::Client::new();
+    let results = service.v1_foods(&client, &[1455408, 173323, 1]).await;
+    assert_eq!(
+        results
+            .iter()
+            .map(|(fdc_id, _)| *fdc_id)
+            .collect::<Vec<_>>(),
+        vec![1455408, 173323, 1]
+    );
+    assert!(matches!(results[0].1, Ok(FDCMeta::Branded(_))));
+    match &results[1].1 {
+        Err(FdcError::Deserialize { path, .. }) => assert_eq!(path, "foodPortions"),
+        res => panic!("Should have been a deserialization error: {:?}", res),
+    };
+    assert!(matches!(results[2].1, Err(FdcError::Missing { fdc_id: 1 })));
+}
+
+#[tokio::test]
+async fn v1_foods_batches_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount("POST", "/v1/foods", vec![MockResponse::json(200, "[]")]);
+
+    // large requests are split into batches FDC accepts
+    let client = reqwest::Client::new();
+    let fdc_ids = (1..=45).collect::<Vec<i32>>();
+    let results = service.v1_foods(&client, &fdc_ids).await;
+    assert_eq!(results.len(), 45);
+    let batches = server
+        .requests()
+        .iter()
+        .map(|request| request.json()["fdcIds"].as_array().unwrap().len())
+        .collect::<Vec<_>>();
+    assert_eq!(batches, vec![20, 20, 5]);
+}
+
+#[tokio::test]
+async fn v1_foods_failed_batch_mock() {
+    let (service, server) = get_mock_service().await;
+    let sr_legacy = fixtures::foods_json()[1].to_string();
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![
+            MockResponse::json(200, "[]"),
+            MockResponse::json(400, ""),
+            MockResponse::json(200, format!("[{}]", sr_legacy)),
+        ],
+    );
+
+    // a failed batch only fails its own foods
+    let client = reqwest::Client::new();
+    let mut fdc_ids = (1..=40).collect::<Vec<i32>>();
+    fdc_ids.push(173323);
+    let results = service.v1_foods(&client, &fdc_ids).await;
+    assert_eq!(results.len(), 41);
+    assert!(matches!(results[0].1, Err(FdcError::Missing { fdc_id: 1 })));
+    for (fdc_id, result) in &results[20..40] {
+        match result {
+            Err(FdcError::Batch { fdc_id: id, source }) => {
+                assert_eq!(id, fdc_id);
+                assert_eq!(source.status().map(|status| status.as_u16()), Some(400));
+            }
+            res => panic!("Should have been a batch error: {:?}", res),
+        }
+    }
+    assert!(matches!(results[40], (173323, Ok(FDCMeta::SRLegacy(_)))));
+}
+
+#[tokio::test]
+async fn v1_food_mock() {
+    let (service, server) = get_mock_service().await;
+    let foods = serde_json::from_str::<serde_json::Value>(fixtures::FOODS).unwrap();
+    server.mount(
+        "GET",
+        "/v1/food/173323",
+        vec![MockResponse::json(200, foods[1].to_string())],
+    );
+    let client = reqwest::Client::new();
+    match service.v1_food(&client, 173323, &[]).await.unwrap() {
+        FDCMeta::SRLegacy(meta) => assert_eq!(meta.food_portions[0].id, 92296),
+        _ => panic!("Should have been an sr legacy food!"),
+    };
+    let request = server.requests().pop().unwrap();
+    assert_eq!(request.query_param("format"), Some("full"));
+    assert_eq!(request.query_param("nutrients"), None);
+
+    // unknown foods are an error
+    assert!(service.v1_food(&client, 1, &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn v1_food_abridged_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount(
+        "GET",
+        "/v1/food/173323",
+        vec![MockResponse::json(200, fixtures::FOOD_ABRIDGED)],
+    );
+    let client = reqwest::Client::new();
+    let food = service
+        .v1_food_abridged(&client, 173323, &[203, 204])
+        .await
+        .unwrap();
+    assert_eq!(food.fdc_id, 173323);
+    assert_eq!(food.data_type, "SR Legacy");
+    assert_eq!(
+        food.food_nutrients[0].nutrient_number.as_deref(),
+        Some("203")
+    );
+    assert_eq!(food.food_nutrients[0].nutrient_name, "Protein");
+    assert_eq!(food.food_nutrients[0].value, 3.3);
+    let request = server.requests().pop().unwrap();
+    assert_eq!(request.query_param("format"), Some("abridged"));
+    assert_eq!(request.query_param("nutrients"), Some("203%2C204"));
+}
+
+#[tokio::test]
+async fn v1_foods_list_all_mock() {
+    let (service, server) = get_mock_service().await;
+    server.mount(
+        "POST",
+        "/v1/foods/list",
+        fixtures::FOODS_LIST
+            .iter()
+            .map(|page| MockResponse::json(200, *page)),
+    );
+    let client = reqwest::Client::new();
+    let criteria = FoodListCriteria::new()
+        .data_types(&[DataType::SRLegacy])
+        .page_size(2);
+    let foods = service
+        .v1_foods_list_all(&client, criteria)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        vec![167512, 167513, 167514]
+    );
+
+    // the short second page ends the stream
+    let bodies = server
+        .requests()
+        .iter()
+        .map(|request| request.json())
+        .collect::<Vec<_>>();
+    assert_eq!(bodies.len(), 2);
+    assert_eq!(
+        bodies[1],
+        serde_json::json!({ "dataType": ["SR Legacy"], "pageSize": 2, "pageNumber": 2 })
+    );
+}
+
+#[tokio::test]
+async fn v1_foods_list_page_size_mock() {
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    let page = |n: usize| {
+        let foods = (0..n)
+            .map(|i| serde_json::json!({ "fdcId": i, "description": "", "dataType": "SR Legacy", "foodNutrients": [] }))
+            .collect::<Vec<_>>();
+        MockResponse::json(200, serde_json::Value::from(foods).to_string())
+    };
+
+    // page sizes beyond what FDC serves are capped, so full pages keep the stream going
+    server.mount("POST", "/v1/foods/list", vec![page(200), page(3)]);
+    let criteria = FoodListCriteria::new().page_size(500);
+    assert_eq!(criteria.page_size, Some(200));
+    let foods = service
+        .v1_foods_list_all(&client, criteria)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(foods.len(), 203);
+    assert_eq!(server.requests()[0].json()["pageSize"], 200);
+
+    // and page sizes below one are raised, so an empty page ends the stream
+    let (service, server) = get_mock_service().await;
+    server.mount("POST", "/v1/foods/list", vec![page(1), page(0)]);
+    let criteria = FoodListCriteria {
+        page_size: Some(0),
+        ..FoodListCriteria::new()
+    };
+    let foods = service
+        .v1_foods_list_all(&client, criteria)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(foods.len(), 1);
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(
+        FoodSearchCriteria::new("milk").page_size(-1).page_size,
+        Some(1)
+    );
+}
+
+#[tokio::test]
+async fn errors_mock() {
+    let (service, server) = get_mock_service().await;
+    let service = service.with_retry_policy(RetryPolicy::none());
+    let client = reqwest::Client::new();
+    server.mount(
+        "POST",
+        "/v1/foods/search",
+        vec![
+            MockResponse::json(403, fixtures::API_KEY_INVALID),
+            MockResponse::json(429, fixtures::OVER_RATE_LIMIT),
+            MockResponse::json(500, "oops"),
+            MockResponse::json(
+                200,
+                r#"{"totalHits": 1, "currentPage": 1, "totalPages": 1,
+                "foods": [{"fdcId": "1", "dataType": "Branded", "description": "",
+                "foodNutrients": []}]}"#,
+            ),
+        ],
+    );
+
+    // an invalid key
+    match service.v1_foods_search(&client, "cheddar").await {
+        Err(FdcError::InvalidApiKey { status, body }) => {
+            assert_eq!(status, 403);
+            assert_eq!(body.code.as_deref(), Some("API_KEY_INVALID"));
+        }
+        res => panic!("Should have been an invalid key: {:?}", res),
+    };
+
+    // the rate limit
+    match service.v1_foods_search(&client, "cheddar").await {
+        Err(FdcError::RateLimited { status, body }) => {
+            assert_eq!(status, 429);
+            assert_eq!(body.code.as_deref(), Some("OVER_RATE_LIMIT"));
+        }
+        res => panic!("Should have been rate limited: {:?}", res),
+    };
+
+    // a server error whose body is not json
+    match service.v1_foods_search(&client, "cheddar").await {
+        Err(e @ FdcError::Status { .. }) => {
+            assert_eq!(e.status().map(|s| s.as_u16()), Some(500));
+            assert_eq!(e.body().map(|b| &b.text[..]), Some("oops"));
+            assert_eq!(
+                e.to_string(),
+                "FDC responded with 500 Internal Server Error: \"oops\""
+            );
+        }
+        res => panic!("Should have been a server error: {:?}", res),
+    };
+
+    // a payload with the wrong type
+    match service.v1_foods_search(&client, "cheddar").await {
+        Err(FdcError::Deserialize { path, .. }) => assert_eq!(path, "foods[0].fdcId"),
+        res => panic!("Should have been a deserialization error: {:?}", res),
+    };
+
+    // an unknown food
+    match service.v1_food(&client, 1, &[]).await {
+        Err(e @ FdcError::NotFound { .. }) => {
+            assert_eq!(e.body().and_then(|b| b.code.as_deref()), Some("NOT_FOUND"));
+        }
+        res => panic!("Should have been not found: {:?}", res),
+    };
+}
+
+#[tokio::test]
+async fn retry_mock() {
+    let (service, server) = get_mock_service().await;
+    let service = service.with_retry_policy(RetryPolicy {
+        max_retries: 2,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    });
+    let client = reqwest::Client::new();
+    assert_eq!(service.rate_limit(), RateLimit::default());
+
+    // a server error and a rate limit are retried, and the budget is tracked
+    server.mount(
+        "POST",
+        "/v1/foods/search",
+        vec![
+            MockResponse::json(503, "").header("X-RateLimit-Remaining", "12"),
+            MockResponse::json(429, fixtures::OVER_RATE_LIMIT)
+                .header("X-RateLimit-Limit", "1000")
+                .header("X-RateLimit-Remaining", "0")
+                .header("Retry-After", "0"),
+            MockResponse::json(200, fixtures::FOODS_SEARCH_UPC)
+                .header("X-RateLimit-Limit", "1000")
+                .header("X-RateLimit-Remaining", "998"),
+        ],
+    );
+    let foods = service.v1_foods_search(&client, "00027000690260").await;
+    assert_eq!(foods.unwrap()[0].fdc_id, 1455408);
+    assert_eq!(server.requests().len(), 3);
+    let rate_limit = service.clone().rate_limit();
+    assert_eq!(rate_limit.limit, Some(1000));
+    assert_eq!(rate_limit.remaining, Some(998));
+    assert!(rate_limit.reported_at.is_some());
+
+    // retries give up after the policy's maximum
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(500, "").header("X-RateLimit-Remaining", "995")],
+    );
+    match &service.v1_foods(&client, &[1]).await[0].1 {
+        Err(FdcError::Batch { fdc_id, source }) => {
+            assert_eq!(*fdc_id, 1);
+            assert_eq!(source.status().map(|status| status.as_u16()), Some(500));
+        }
+        res => panic!("Should have been a server error: {:?}", res),
+    };
+    assert_eq!(server.requests().len(), 6);
+    assert_eq!(service.rate_limit().remaining, Some(995));
+
+    // a rate limit asking to wait longer than the policy allows is not retried
+    server.mount(
+        "POST",
+        "/v1/foods/list",
+        vec![MockResponse::json(429, fixtures::OVER_RATE_LIMIT).header("Retry-After", "3600")],
+    );
+    match service
+        .v1_foods_list(&client, &FoodListCriteria::new())
+        .await
+    {
+        Err(FdcError::RateLimited { .. }) => {}
+        res => panic!("Should have been rate limited: {:?}", res),
+    };
+    assert_eq!(server.requests().len(), 7);
+
+    // client errors are never retried
+    match service.v1_food(&client, 1, &[]).await {
+        Err(FdcError::NotFound { .. }) => {}
+        res => panic!("Should have been not found: {:?}", res),
+    };
+    assert_eq!(server.requests().len(), 8);
+}
+
+#[tokio::test]
+async fn exhausted_mock() {
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    server.mount(
+        "POST",
+        "/v1/foods/search",
+        vec![MockResponse::json(200, fixtures::FOODS_SEARCH_UPC)
+            .header("X-RateLimit-Limit", "1000")
+            .header("X-RateLimit-Remaining", "0")],
+    );
+
+    // the last request of the budget goes through
+    assert!(service.v1_foods_search(&client, "cheddar").await.is_ok());
+    assert_eq!(server.requests().len(), 1);
+
+    // but later ones fail without being sent
+    match service.v1_foods_search(&client, "cheddar").await {
+        Err(e @ FdcError::Exhausted) => assert_eq!(e.status(), None),
+        res => panic!("Should have been exhausted: {:?}", res),
+    };
+    assert!(matches!(
+        service.clone().v1_food(&client, 1, &[]).await,
+        Err(FdcError::Exhausted)
+    ));
+    assert_eq!(server.requests().len(), 1);
+
+    // until the window of the report has passed
+    let rate_limit = service.rate_limit();
+    let reported_at = rate_limit.reported_at.unwrap();
+    assert!(rate_limit.is_exhausted(reported_at + Duration::from_secs(60)));
+    assert!(!rate_limit.is_exhausted(reported_at + RateLimit::WINDOW));
+    let rate_limit = RateLimit {
+        remaining: Some(1),
+        ..rate_limit
+    };
+    assert!(!rate_limit.is_exhausted(reported_at));
+}
+
+#[test]
+fn retry_delay() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+    };
+    let headers = reqwest::header::HeaderMap::new();
+    for attempt in 0..10 {
+        let bound = Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_secs(1));
+        assert!(policy.delay(attempt, &headers).unwrap() <= bound);
+    }
+    assert_eq!(policy.delay(10, &headers), None);
+    assert_eq!(RetryPolicy::none().delay(0, &headers), None);
+}
+
+#[tokio::test]
+async fn network_error() {
+    // nothing listens on the discard port
+    let service = FDCService::new("KEY").with_base_url("http://127.0.0.1:9");
+    let client = reqwest::Client::new();
+    match service.v1_foods_search(&client, "cheddar").await {
+        Err(e @ FdcError::Network(_)) => assert_eq!(e.status(), None),
+        res => panic!("Should have been a network error: {:?}", res),
+    };
+}
+
+#[test]
+fn base_url() {
+    let service = FDCService::new("KEY");
+    assert_eq!(service.base_url, "https://api.nal.usda.gov/fdc");
+    assert_eq!(
+        service.url("v1/foods"),
+        "https://api.nal.usda.gov/fdc/v1/foods?api_key=KEY"
+    );
+    let service = service.with_base_url("http://127.0.0.1:8080/");
+    assert_eq!(
+        service.url("v1/foods"),
+        "http://127.0.0.1:8080/v1/foods?api_key=KEY"
+    );
+}
+
+#[test]
+fn sparse_foods() {
+    // foods without attributes, portions or the details of their nutrients still deserialize
+    for data_type in &["Foundation", "SR Legacy", "Survey (FNDDS)"] {
+        let food = serde_json::json!({
+            "fdcId": 1,
+            "dataType": data_type,
+            "description": "sparse",
+            "foodNutrients": [{ "amount": 1.0 }],
+        });
+        let meta = crate::fdc::meta_from_value(food).unwrap();
+        assert!(meta.food_portions().is_empty());
+        assert!(meta.food_nutrients()[0].nutrient.is_none());
+    }
+    // as do branded foods without ingredients, a serving size or nutrients
+    let food = serde_json::json!({ "fdcId": 1, "dataType": "Branded", "description": "sparse" });
+    match crate::fdc::meta_from_value(food).unwrap() {
+        FDCMeta::Branded(meta) => {
+            assert!(meta.ingredients.is_none() && meta.food_nutrients.is_empty());
+            assert_eq!(
+                meta.serving_size(),
+                Err(crate::quantities::serving::ServingError::NoSize)
+            );
+        }
+        _ => panic!("Should have been a branded food!"),
+    };
+}
+
+#[test]
+fn serialize_round_trip() {
+    let foods = fixtures::foods_json();
+    for food in foods {
+        let meta = crate::fdc::meta_from_value(food).unwrap();
+        let json = serde_json::to_value(&meta).unwrap();
+        let data_type = serde_json::to_value(meta.data_type()).unwrap();
+        assert_eq!(json["dataType"], data_type);
+        assert_eq!(json["fdcId"], meta.fdc_id());
+        assert!(json.get("fdc_id").is_none());
+        let again = serde_json::from_value::<FDCMeta>(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), json);
+        // our payloads read back just like those of the api
+        let again = crate::fdc::meta_from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), json);
+    }
+}
+
+#[test]
+fn serialize_payloads() {
+    let search =
+        serde_json::from_str::<crate::fdc::SearchResult>(fixtures::FOODS_SEARCH_UPC).unwrap();
+    let json = serde_json::to_value(&search).unwrap();
+    assert_eq!(json["totalHits"], search.total_hits);
+    let nutrient = &json["foods"][0]["foodNutrients"][0];
+    assert!(nutrient["nutrientName"].is_string());
+    let again = serde_json::from_value::<crate::fdc::SearchResult>(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&again).unwrap(), json);
+
+    for (unit, name) in [
+        (NutrientUnit::Milligram, "MG"),
+        (NutrientUnit::Kilojoule, "kJ"),
+        (NutrientUnit::Other("SP_GR".into()), "SP_GR"),
+    ] {
+        let json = serde_json::to_value(&unit).unwrap();
+        assert_eq!(json, name);
+        assert_eq!(serde_json::from_value::<NutrientUnit>(json).unwrap(), unit);
+    }
+}
@@ -0,0 +1,14 @@
+//! Nutritional data of the foods FoodData Central (FDC) publishes: requests to its api, a
+//! store of the foods we have seen, and the quantities and nutrients of their labels.
+
+#[macro_use]
+extern crate serde;
+
+pub mod env;
+pub mod fdc;
+pub mod nutrients;
+pub mod quantities;
+pub mod store;
+
+#[cfg(test)]
+mod testing;
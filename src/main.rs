@@ -1,8 +1 @@
-#[macro_use]
-extern crate serde;
-
-mod env;
-mod fdc;
-mod quantities;
-
 fn main() {}
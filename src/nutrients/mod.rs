@@ -0,0 +1,423 @@
+//! This module computes the nutrients in a [`Quantity`] of a food. FDC reports the nutrients of
+//! most foods per 100 g, those of branded foods per 100 g or 100 ml of their serving size unit,
+//! and the label nutrients of branded foods per serving. The [`Basis`] of a food's nutrients is
+//! normalised against the quantity, so that [`nutrients`] can scale them into a
+//! [`NutrientProfile`] whose amounts carry their units through `uom`.
+
+use crate::fdc::{AbridgedFoodItem, BrandedFoodItem, FDCMeta, LabelNutrient, NutrientUnit};
+use crate::quantities::convert::ConversionError;
+use crate::quantities::parse;
+use crate::quantities::serving::ServingError;
+use crate::quantities::Quantity;
+
+use uom::si::energy::{kilocalorie, kilojoule};
+use uom::si::f32::{Energy, Mass, MassDensity, Volume};
+use uom::si::mass::{gram, microgram, milligram};
+use uom::si::volume::milliliter;
+
+/// The ways the nutrients of a quantity can fail to be computed.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum NutrientError {
+    #[error(transparent)]
+    Conversion(#[from] ConversionError),
+    #[error(transparent)]
+    Serving(#[from] ServingError),
+}
+
+type Result<T> = std::result::Result<T, NutrientError>;
+
+/// The amount of a nutrient, in the kind of unit FDC reports it with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Amount {
+    Mass(Mass),
+    Energy(Energy),
+    /// an amount of a vitamin in international units, which measure its biological activity
+    InternationalUnits(f32),
+    /// an amount in a unit we do not interpret, like `"SP_GR"`
+    Other(f32, String),
+}
+
+impl Amount {
+    /// the amount of a value in a unit of FDC
+    pub fn new(value: f32, unit: &NutrientUnit) -> Amount {
+        match unit {
+            NutrientUnit::Gram => Amount::Mass(Mass::new::<gram>(value)),
+            NutrientUnit::Milligram => Amount::Mass(Mass::new::<milligram>(value)),
+            NutrientUnit::Microgram => Amount::Mass(Mass::new::<microgram>(value)),
+            NutrientUnit::Kilocalorie => Amount::Energy(Energy::new::<kilocalorie>(value)),
+            NutrientUnit::Kilojoule => Amount::Energy(Energy::new::<kilojoule>(value)),
+            NutrientUnit::InternationalUnit => Amount::InternationalUnits(value),
+            NutrientUnit::Other(unit) => Amount::Other(value, unit.clone()),
+        }
+    }
+
+    /// the amount multiplied by a factor
+    pub fn scale(&self, factor: f32) -> Amount {
+        match self {
+            Amount::Mass(mass) => Amount::Mass(*mass * factor),
+            Amount::Energy(energy) => Amount::Energy(*energy * factor),
+            Amount::InternationalUnits(value) => Amount::InternationalUnits(value * factor),
+            Amount::Other(value, unit) => Amount::Other(value * factor, unit.clone()),
+        }
+    }
+
+    /// The sum of two amounts, if they are of the same kind. Energies in kcal and kJ add up, as
+    /// do masses in any unit, but an amount in international units only adds to another of the
+    /// same vitamin; see [`NutrientAmount::normalize`] to turn it into a mass first.
+    pub fn checked_add(&self, other: &Amount) -> Option<Amount> {
+        match (self, other) {
+            (Amount::Mass(left), Amount::Mass(right)) => Some(Amount::Mass(*left + *right)),
+            (Amount::Energy(left), Amount::Energy(right)) => Some(Amount::Energy(*left + *right)),
+            (Amount::InternationalUnits(left), Amount::InternationalUnits(right)) => {
+                Some(Amount::InternationalUnits(left + right))
+            }
+            (Amount::Other(left, left_unit), Amount::Other(right, right_unit))
+                if left_unit == right_unit =>
+            {
+                Some(Amount::Other(left + right, left_unit.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The vitamins FDC reports in international units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Vitamin {
+    A,
+    D,
+    E,
+}
+
+impl Vitamin {
+    /// the vitamin of a nutrient, by its number or else its name
+    pub fn of(number: Option<&str>, name: &str) -> Option<Vitamin> {
+        match number {
+            Some("318") | Some("319") | Some("320") => return Some(Vitamin::A),
+            Some("324") | Some("325") | Some("328") => return Some(Vitamin::D),
+            Some("323") | Some("340") | Some("573") => return Some(Vitamin::E),
+            _ => {}
+        }
+        let name = name.to_lowercase();
+        if name.starts_with("vitamin a") || name.starts_with("retinol") {
+            Some(Vitamin::A)
+        } else if name.starts_with("vitamin d") {
+            Some(Vitamin::D)
+        } else if name.starts_with("vitamin e") || name.contains("tocopherol") {
+            Some(Vitamin::E)
+        } else {
+            None
+        }
+    }
+
+    /// The mass of one international unit of the vitamin. Vitamin A is taken as retinol and
+    /// vitamin E as natural alpha-tocopherol, the forms the FDA label converts with.
+    pub fn per_international_unit(&self) -> Mass {
+        match self {
+            Vitamin::A => Mass::new::<microgram>(0.3),
+            Vitamin::D => Mass::new::<microgram>(0.025),
+            Vitamin::E => Mass::new::<milligram>(0.67),
+        }
+    }
+}
+
+/// The amount of a single nutrient. Nutrients are identified by the `number` FDC gives them,
+/// like `"203"` for protein, as the ids of the label nutrients are not known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NutrientAmount {
+    pub number: Option<String>,
+    pub name: String,
+    pub amount: Amount,
+}
+
+impl NutrientAmount {
+    /// the vitamin the nutrient is, if it is one FDC reports in international units
+    pub fn vitamin(&self) -> Option<Vitamin> {
+        Vitamin::of(self.number.as_deref(), &self.name)
+    }
+
+    /// The nutrient with international units of a known vitamin converted to a mass.
+    pub fn normalize(&self) -> NutrientAmount {
+        match (&self.amount, self.vitamin()) {
+            (Amount::InternationalUnits(value), Some(vitamin)) => NutrientAmount {
+                amount: Amount::Mass(vitamin.per_international_unit() * *value),
+                ..self.clone()
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+/// The nutrients in some amount of a food.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NutrientProfile {
+    pub nutrients: Vec<NutrientAmount>,
+}
+
+impl NutrientProfile {
+    /// the nutrient with a number, like `"203"` for protein
+    pub fn get(&self, number: &str) -> Option<&NutrientAmount> {
+        self.nutrients
+            .iter()
+            .find(|nutrient| nutrient.number.as_deref() == Some(number))
+    }
+
+    /// the nutrient with a name, ignoring case
+    pub fn by_name(&self, name: &str) -> Option<&NutrientAmount> {
+        self.nutrients
+            .iter()
+            .find(|nutrient| nutrient.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The nutrients of the profiles added up, with international units of vitamins converted
+    /// to masses. Nutrients are matched by number, or by name when they have none; amounts of
+    /// the same nutrient which are still of different kinds are kept apart.
+    pub fn sum<'a, I>(profiles: I) -> NutrientProfile
+    where
+        I: IntoIterator<Item = &'a NutrientProfile>,
+    {
+        let mut total = NutrientProfile::default();
+        for nutrient in profiles.into_iter().flat_map(|profile| &profile.nutrients) {
+            let nutrient = nutrient.normalize();
+            let same = total.nutrients.iter_mut().find_map(|other| {
+                let same_nutrient = match (&nutrient.number, &other.number) {
+                    (Some(number), Some(other_number)) => number == other_number,
+                    (None, None) => nutrient.name.eq_ignore_ascii_case(&other.name),
+                    _ => false,
+                };
+                let sum = other.amount.checked_add(&nutrient.amount);
+                match sum {
+                    Some(sum) if same_nutrient => Some((other, sum)),
+                    _ => None,
+                }
+            });
+            match same {
+                Some((other, sum)) => other.amount = sum,
+                None => total.nutrients.push(nutrient),
+            }
+        }
+        total
+    }
+
+    /// every nutrient multiplied by a factor
+    pub fn scale(&self, factor: f32) -> NutrientProfile {
+        NutrientProfile {
+            nutrients: self
+                .nutrients
+                .iter()
+                .map(|nutrient| NutrientAmount {
+                    amount: nutrient.amount.scale(factor),
+                    ..nutrient.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The amount of a food its nutrients are reported for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Basis {
+    Mass(Mass),
+    Volume(Volume),
+}
+
+impl Basis {
+    /// the basis of most foods
+    pub fn per_100_grams() -> Basis {
+        Basis::Mass(Mass::new::<gram>(100.0))
+    }
+
+    /// the basis of branded foods served by volume
+    pub fn per_100_milliliters() -> Basis {
+        Basis::Volume(Volume::new::<milliliter>(100.0))
+    }
+
+    /// How many times the quantity holds the basis, using the density to go between masses and
+    /// volumes.
+    pub fn factor(&self, quantity: &Quantity, density: Option<MassDensity>) -> Result<f32> {
+        match (self, quantity) {
+            (Basis::Mass(basis), quantity) => Ok((quantity.to_mass(density)? / *basis).value),
+            (Basis::Volume(basis), Quantity::Volume(volume)) => Ok((*volume / *basis).value),
+            (Basis::Volume(basis), Quantity::Mass(mass)) => match density {
+                Some(density) => Ok((*mass / density / *basis).value),
+                None => Err(ConversionError::NoDensity.into()),
+            },
+            (Basis::Volume(_), Quantity::Nominal(_, unit)) => {
+                Err(ConversionError::Nominal(unit.clone()).into())
+            }
+        }
+    }
+}
+
+/// The nutrients FDC reports for a food, along with their basis. Branded foods without a
+/// nutrient list fall back to their label nutrients, which are reported per serving, so only
+/// they need a serving size we can read.
+pub fn reported(food: &FDCMeta) -> Result<(Basis, NutrientProfile)> {
+    let profile = NutrientProfile {
+        nutrients: food
+            .food_nutrients()
+            .iter()
+            .filter_map(|food_nutrient| {
+                let amount = food_nutrient.amount?;
+                let nutrient = food_nutrient.nutrient.as_ref()?;
+                Some(NutrientAmount {
+                    number: nutrient.number.clone(),
+                    name: nutrient.name.clone(),
+                    amount: Amount::new(amount, &nutrient.unit_name),
+                })
+            })
+            .collect(),
+    };
+    let meta = match food {
+        FDCMeta::Branded(meta) => meta,
+        _ => return Ok((Basis::per_100_grams(), profile)),
+    };
+    if !profile.nutrients.is_empty() || meta.label_nutrients.is_none() {
+        let basis = if served_by_volume(meta) {
+            Basis::per_100_milliliters()
+        } else {
+            Basis::per_100_grams()
+        };
+        return Ok((basis, profile));
+    }
+    match meta.serving_size()? {
+        Quantity::Mass(mass) => Ok((Basis::Mass(mass), label_profile(meta))),
+        Quantity::Volume(volume) => Ok((Basis::Volume(volume), label_profile(meta))),
+        Quantity::Nominal(_, _) => Ok((Basis::per_100_grams(), profile)),
+    }
+}
+
+/// helper function which decides whether a branded food is served by volume from the unit of
+/// its serving size alone, so that labels without a readable serving size are served by mass
+fn served_by_volume(meta: &BrandedFoodItem) -> bool {
+    let unit = match meta.serving_size_unit.as_deref() {
+        Some(unit) => unit.trim(),
+        None => return false,
+    };
+    matches!(
+        parse::parse_measure(&format!("1 {}", unit)),
+        Ok(Quantity::Volume(_))
+    )
+}
+
+/// The nutrients in a quantity of a food. Nominal quantities are resolved against the portions
+/// of the food, or for branded foods against the household serving of their label. The household
+/// serving is only read when the quantity needs it, so that labels we cannot read still give the
+/// nutrients of masses and volumes.
+pub fn nutrients(food: &FDCMeta, quantity: &Quantity) -> Result<NutrientProfile> {
+    let (basis, profile) = reported(food)?;
+    let factor = match (food, quantity) {
+        (FDCMeta::Branded(meta), Quantity::Nominal(_, _)) => {
+            let serving = meta.serving()?;
+            basis.factor(&serving.resolve(quantity), serving.density())?
+        }
+        (FDCMeta::Branded(meta), _) => match basis.factor(quantity, None) {
+            Err(NutrientError::Conversion(ConversionError::NoDensity)) => {
+                let density = meta.serving().ok().and_then(|serving| serving.density());
+                basis.factor(quantity, density)?
+            }
+            factor => factor?,
+        },
+        (_, _) => basis.factor(&Quantity::Mass(quantity.to_mass_of(food)?), None)?,
+    };
+    Ok(profile.scale(factor))
+}
+
+/// The nutrients in a mass of an abridged food, whose nutrients are reported per 100 g.
+pub fn abridged_nutrients(food: &AbridgedFoodItem, mass: Mass) -> NutrientProfile {
+    let profile = NutrientProfile {
+        nutrients: food
+            .food_nutrients
+            .iter()
+            .map(|food_nutrient| NutrientAmount {
+                number: food_nutrient.nutrient_number.clone(),
+                name: food_nutrient.nutrient_name.clone(),
+                amount: Amount::new(food_nutrient.value, &food_nutrient.unit_name),
+            })
+            .collect(),
+    };
+    profile.scale(mass.get::<gram>() / 100.0)
+}
+
+/// The label nutrients of a branded food, per serving.
+pub fn label_profile(food: &BrandedFoodItem) -> NutrientProfile {
+    let label = match &food.label_nutrients {
+        Some(label) => label,
+        None => return NutrientProfile::default(),
+    };
+    // the label gives no units, so they are the ones of the FDA label
+    let entries: [(&Option<LabelNutrient>, Option<&str>, &str, &str); 22] = [
+        (&label.calories, Some("208"), "Energy", "kcal"),
+        (&label.fat, Some("204"), "Total lipid (fat)", "g"),
+        (
+            &label.saturated_fat,
+            Some("606"),
+            "Fatty acids, total saturated",
+            "g",
+        ),
+        (
+            &label.trans_fat,
+            Some("605"),
+            "Fatty acids, total trans",
+            "g",
+        ),
+        (
+            &label.polyunsaturated_fat,
+            Some("646"),
+            "Fatty acids, total polyunsaturated",
+            "g",
+        ),
+        (
+            &label.monounsaturated_fat,
+            Some("645"),
+            "Fatty acids, total monounsaturated",
+            "g",
+        ),
+        (&label.cholesterol, Some("601"), "Cholesterol", "mg"),
+        (&label.sodium, Some("307"), "Sodium, Na", "mg"),
+        (
+            &label.carbohydrates,
+            Some("205"),
+            "Carbohydrate, by difference",
+            "g",
+        ),
+        (&label.fiber, Some("291"), "Fiber, total dietary", "g"),
+        (&label.soluble_fiber, None, "Fiber, soluble", "g"),
+        (&label.insoluble_fiber, None, "Fiber, insoluble", "g"),
+        (
+            &label.sugars,
+            Some("269"),
+            "Sugars, total including NLEA",
+            "g",
+        ),
+        (&label.added_sugar, Some("539"), "Sugars, added", "g"),
+        (&label.sugar_alcohol, None, "Sugar alcohol", "g"),
+        (&label.protein, Some("203"), "Protein", "g"),
+        (&label.vitamin_d, Some("328"), "Vitamin D (D2 + D3)", "µg"),
+        (&label.calcium, Some("301"), "Calcium, Ca", "mg"),
+        (&label.iron, Some("303"), "Iron, Fe", "mg"),
+        (&label.potassium, Some("306"), "Potassium, K", "mg"),
+        (&label.vitamin_a, Some("320"), "Vitamin A, RAE", "µg"),
+        (
+            &label.vitamin_c,
+            Some("401"),
+            "Vitamin C, total ascorbic acid",
+            "mg",
+        ),
+    ];
+    NutrientProfile {
+        nutrients: entries
+            .iter()
+            .filter_map(|(nutrient, number, name, unit)| {
+                nutrient.as_ref().map(|nutrient| NutrientAmount {
+                    number: number.map(String::from),
+                    name: name.to_string(),
+                    amount: Amount::new(nutrient.value, &NutrientUnit::from(*unit)),
+                })
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test;
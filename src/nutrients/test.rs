@@ -0,0 +1,211 @@
+use super::*;
+use crate::fdc::{mock::fixtures, NutrientUnit};
+use crate::testing::assert_close;
+
+use uom::si::volume::{cup, tablespoon};
+
+/// helper function which gets the grams of a nutrient in a profile
+fn grams(profile: &NutrientProfile, number: &str) -> f32 {
+    match &profile.get(number).unwrap().amount {
+        Amount::Mass(mass) => mass.get::<gram>(),
+        amount => panic!("Should have been a mass: {:?}", amount),
+    }
+}
+
+#[test]
+fn per_100_grams() {
+    let foods = fixtures::foods();
+
+    // masses scale the nutrients directly
+    let flour = nutrients(&foods[3], &Quantity::Mass(Mass::new::<gram>(150.0))).unwrap();
+    assert_close(grams(&flour, "203"), 15.45);
+
+    // volumes go through the portions
+    let milk = nutrients(&foods[1], &Quantity::Volume(Volume::new::<cup>(1.0))).unwrap();
+    assert_close(grams(&milk, "203"), 8.052);
+    match &milk.by_name("energy").unwrap().amount {
+        Amount::Energy(energy) => assert_close(energy.get::<kilocalorie>(), 122.0),
+        amount => panic!("Should have been an energy: {:?}", amount),
+    }
+
+    // and so do nominal quantities
+    let eggs = nutrients(&foods[2], &Quantity::Nominal(2.0, "large eggs".into())).unwrap();
+    assert_close(grams(&eggs, "203"), 12.5294);
+}
+
+#[test]
+fn branded() {
+    let foods = fixtures::foods();
+
+    // the oil is reported per 100 ml
+    assert_eq!(reported(&foods[0]).unwrap().0, Basis::per_100_milliliters());
+    let spoon = Quantity::Volume(Volume::new::<tablespoon>(1.0));
+    let oil = nutrients(&foods[0], &spoon).unwrap();
+    assert_close(grams(&oil, "204"), 14.7868);
+
+    // servings are in terms of the label
+    let servings = nutrients(&foods[0], &Quantity::Nominal(2.0, "servings".into())).unwrap();
+    assert_close(grams(&servings, "204"), 28.0);
+
+    // but the label does not give the density of the oil
+    assert_eq!(
+        nutrients(&foods[0], &Quantity::Mass(Mass::new::<gram>(10.0))),
+        Err(NutrientError::Conversion(ConversionError::NoDensity))
+    );
+}
+
+#[test]
+fn label_fallback() {
+    let mut meta = match fixtures::foods().remove(0) {
+        FDCMeta::Branded(meta) => meta,
+        _ => panic!("Should have been a branded food!"),
+    };
+    meta.food_nutrients.clear();
+    let food = FDCMeta::Branded(meta);
+
+    // the label nutrients are per serving
+    assert_eq!(
+        reported(&food).unwrap().0,
+        Basis::Volume(Volume::new::<milliliter>(14.0))
+    );
+    let serving = nutrients(&food, &Quantity::Nominal(1.0, "serving".into())).unwrap();
+    assert_close(grams(&serving, "204"), 13.9995);
+    match &serving.get("208").unwrap().amount {
+        Amount::Energy(energy) => assert_close(energy.get::<kilocalorie>(), 120.0),
+        amount => panic!("Should have been an energy: {:?}", amount),
+    }
+}
+
+#[test]
+fn unreadable_serving_size() {
+    let oil = |edit: &dyn Fn(&mut BrandedFoodItem)| match fixtures::foods().remove(0) {
+        FDCMeta::Branded(mut meta) => {
+            meta.serving_size = None;
+            edit(&mut meta);
+            FDCMeta::Branded(meta)
+        }
+        _ => panic!("Should have been a branded food!"),
+    };
+
+    // the nutrient list only needs the unit of the serving size
+    let food = oil(&|_| {});
+    assert_eq!(reported(&food).unwrap().0, Basis::per_100_milliliters());
+    let spoon = Quantity::Volume(Volume::new::<tablespoon>(1.0));
+    assert_close(grams(&nutrients(&food, &spoon).unwrap(), "204"), 14.7868);
+    let food = oil(&|meta| meta.serving_size_unit = Some("SEE PACKAGE".into()));
+    assert_eq!(reported(&food).unwrap().0, Basis::per_100_grams());
+
+    // but the label nutrients need the serving size itself
+    let food = oil(&|meta| meta.food_nutrients.clear());
+    assert_eq!(
+        reported(&food),
+        Err(NutrientError::Serving(ServingError::NoSize))
+    );
+}
+
+#[test]
+fn unreadable_household() {
+    let mut meta = match fixtures::foods().remove(0) {
+        FDCMeta::Branded(meta) => meta,
+        _ => panic!("Should have been a branded food!"),
+    };
+    meta.serving_size = Some(14.0);
+    meta.serving_size_unit = Some("g".into());
+    meta.household_serving_full_text = Some("SEE PACKAGE".into());
+    let food = FDCMeta::Branded(meta);
+    let fat = grams(&reported(&food).unwrap().1, "204");
+
+    // masses do not need the household serving
+    let profile = nutrients(&food, &Quantity::Mass(Mass::new::<gram>(150.0))).unwrap();
+    assert_close(grams(&profile, "204"), 1.5 * fat);
+
+    // but nominal quantities do
+    assert!(matches!(
+        nutrients(&food, &Quantity::Nominal(1.0, "serving".into())),
+        Err(NutrientError::Serving(ServingError::Household(_)))
+    ));
+}
+
+#[test]
+fn abridged() {
+    let food = serde_json::from_str::<AbridgedFoodItem>(fixtures::FOOD_ABRIDGED).unwrap();
+    let profile = abridged_nutrients(&food, Mass::new::<gram>(50.0));
+    assert_close(grams(&profile, "203"), 1.65);
+    assert_close(grams(&profile, "204"), 0.99);
+}
+
+#[test]
+fn amounts() {
+    assert_eq!(
+        Amount::new(2.0, &"MG".into()),
+        Amount::Mass(Mass::new::<milligram>(2.0))
+    );
+    assert_eq!(
+        Amount::new(2.0, &"IU".into()).scale(2.0),
+        Amount::InternationalUnits(4.0)
+    );
+    assert_eq!(
+        Amount::new(1.03, &"SP_GR".into()),
+        Amount::Other(1.03, "SP_GR".into())
+    );
+
+    // energies add up in any unit
+    let kcal = Amount::new(50.0, &NutrientUnit::Kilocalorie);
+    let kj = Amount::new(209.0, &NutrientUnit::Kilojoule);
+    match kcal.checked_add(&kj) {
+        Some(Amount::Energy(energy)) => {
+            assert_close(energy.get::<kilocalorie>(), 99.952);
+            assert_close(energy.get::<kilojoule>(), 418.2);
+        }
+        sum => panic!("Should have been an energy: {:?}", sum),
+    }
+    assert_eq!(kcal.checked_add(&Amount::InternationalUnits(1.0)), None);
+}
+
+#[test]
+fn international_units() {
+    let vitamin = |number: &str, name: &str, value: f32| NutrientAmount {
+        number: Some(number.into()),
+        name: name.into(),
+        amount: Amount::InternationalUnits(value),
+    };
+    let micrograms = |nutrient: NutrientAmount| match nutrient.normalize().amount {
+        Amount::Mass(mass) => mass.get::<microgram>(),
+        amount => panic!("Should have been a mass: {:?}", amount),
+    };
+    assert_close(micrograms(vitamin("318", "Vitamin A, IU", 1000.0)), 300.0);
+    assert_close(
+        micrograms(vitamin(
+            "324",
+            "Vitamin D (D2 + D3), International Units",
+            400.0,
+        )),
+        10.0,
+    );
+    assert_close(micrograms(vitamin("", "Vitamin E", 30.0)), 20100.0);
+
+    // units of other nutrients are left alone
+    let unknown = vitamin("999", "Something", 3.0);
+    assert_eq!(unknown.normalize(), unknown);
+}
+
+#[test]
+fn sums() {
+    let foods = fixtures::foods();
+    let milk = nutrients(&foods[1], &Quantity::Mass(Mass::new::<gram>(200.0))).unwrap();
+    let more_milk = nutrients(&foods[1], &Quantity::Mass(Mass::new::<gram>(100.0))).unwrap();
+    let total = NutrientProfile::sum(vec![&milk, &more_milk]);
+    assert_close(grams(&total, "203"), 9.9);
+
+    // kcal and kJ stay separate nutrients
+    match &total.get("268").unwrap().amount {
+        Amount::Energy(energy) => assert_close(energy.get::<kilojoule>(), 627.0),
+        amount => panic!("Should have been an energy: {:?}", amount),
+    }
+
+    // and the vitamin D the milk reports in IU is now a mass
+    match &total.get("324").unwrap().amount {
+        Amount::Mass(mass) => assert_close(mass.get::<microgram>(), 3.525),
+        amount => panic!("Should have been a mass: {:?}", amount),
+    }
+}
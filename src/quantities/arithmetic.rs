@@ -0,0 +1,113 @@
+//! This module adds, scales and compares quantities. Volumes add to volumes and masses to
+//! masses, while nominal quantities only add to quantities of the same unit. Volumes and masses
+//! add to one another through the density of the food, as [`Quantity::add_with`] does.
+
+use super::Quantity;
+
+use std::cmp::Ordering;
+use std::ops::Mul;
+
+use uom::si::f32::MassDensity;
+
+/// The ways quantities can fail to add.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ArithmeticError {
+    #[error("cannot add a {1} to a {0} without the density of the food")]
+    Dimensions(&'static str, &'static str),
+    #[error("cannot add a nominal quantity of {1:?} to one of {0:?}")]
+    Units(String, String),
+}
+
+impl Quantity {
+    /// the name of the kind of the quantity
+    fn kind(&self) -> &'static str {
+        match self {
+            Quantity::Volume(_) => "volume",
+            Quantity::Mass(_) => "mass",
+            Quantity::Nominal(_, _) => "nominal quantity",
+        }
+    }
+
+    /// Add a quantity of the same kind, with nominal quantities needing the same unit.
+    pub fn checked_add(&self, other: &Quantity) -> Result<Quantity, ArithmeticError> {
+        self.add_with(other, None)
+    }
+
+    /// Add a quantity, converting between volume and mass with the density of the food when
+    /// there is one. The sum is of the kind of `self`.
+    pub fn add_with(
+        &self,
+        other: &Quantity,
+        density: Option<MassDensity>,
+    ) -> Result<Quantity, ArithmeticError> {
+        match (self, other, density) {
+            (Quantity::Volume(left), Quantity::Volume(right), _) => {
+                Ok(Quantity::Volume(*left + *right))
+            }
+            (Quantity::Mass(left), Quantity::Mass(right), _) => Ok(Quantity::Mass(*left + *right)),
+            (Quantity::Mass(left), Quantity::Volume(right), Some(density)) => {
+                Ok(Quantity::Mass(*left + density * *right))
+            }
+            (Quantity::Volume(left), Quantity::Mass(right), Some(density)) => {
+                Ok(Quantity::Volume(*left + *right / density))
+            }
+            (Quantity::Nominal(left, unit), Quantity::Nominal(right, other_unit), _) => {
+                if unit == other_unit {
+                    Ok(Quantity::Nominal(left + right, unit.clone()))
+                } else {
+                    Err(ArithmeticError::Units(unit.clone(), other_unit.clone()))
+                }
+            }
+            _ => Err(ArithmeticError::Dimensions(self.kind(), other.kind())),
+        }
+    }
+
+    /// The totals of the quantities, adding each to the first total it can be added to. Totals
+    /// of different kinds or units are kept apart, in the order they first appear.
+    pub fn totals<'a, I>(quantities: I, density: Option<MassDensity>) -> Vec<Quantity>
+    where
+        I: IntoIterator<Item = &'a Quantity>,
+    {
+        let mut totals: Vec<Quantity> = Vec::new();
+        for quantity in quantities {
+            let sum = totals
+                .iter_mut()
+                .find_map(|total| Some((total.add_with(quantity, density).ok()?, total)));
+            match sum {
+                Some((sum, total)) => *total = sum,
+                None => totals.push(quantity.clone()),
+            }
+        }
+        totals
+    }
+}
+
+/// Quantities scale by a factor, like a recipe does.
+impl Mul<f32> for Quantity {
+    type Output = Quantity;
+
+    fn mul(self, factor: f32) -> Quantity {
+        match self {
+            Quantity::Volume(volume) => Quantity::Volume(volume * factor),
+            Quantity::Mass(mass) => Quantity::Mass(mass * factor),
+            Quantity::Nominal(amount, unit) => Quantity::Nominal(amount * factor, unit),
+        }
+    }
+}
+
+/// Quantities compare when they are of the same kind, and nominal quantities when they are of
+/// the same unit.
+impl PartialOrd for Quantity {
+    fn partial_cmp(&self, other: &Quantity) -> Option<Ordering> {
+        match (self, other) {
+            (Quantity::Volume(left), Quantity::Volume(right)) => left.partial_cmp(right),
+            (Quantity::Mass(left), Quantity::Mass(right)) => left.partial_cmp(right),
+            (Quantity::Nominal(left, unit), Quantity::Nominal(right, other_unit))
+                if unit == other_unit =>
+            {
+                left.partial_cmp(right)
+            }
+            _ => None,
+        }
+    }
+}
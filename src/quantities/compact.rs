@@ -0,0 +1,62 @@
+//! This module provides the compact string form of a [`Quantity`], like `"1.5 cup"`. Any text
+//! the [`parse::parse_quantity`] parser accepts reads as a quantity, while quantities are written
+//! with masses in grams and volumes in milliliters. Fields can opt into the form with
+//! `#[serde(with = "crate::quantities::compact")]`.
+
+use super::parse::{self, ParseError};
+use super::Quantity;
+
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use uom::si::mass::gram;
+use uom::si::volume::milliliter;
+
+/// The significant digits of the amounts we write. An `f32` carries about seven, the last of
+/// which unit conversions disturb.
+const SIGNIFICANT_DIGITS: usize = 6;
+
+/// helper function which writes an amount without the noise of unit conversions
+fn amount(value: f32) -> f32 {
+    format!("{:.*e}", SIGNIFICANT_DIGITS - 1, value)
+        .parse()
+        .unwrap_or(value)
+}
+
+impl Quantity {
+    /// The compact string form of the quantity, like `"28 g"` or `"2 slices"`.
+    pub fn compact(&self) -> String {
+        match self {
+            Quantity::Mass(mass) => format!("{} g", amount(mass.get::<gram>())),
+            Quantity::Volume(volume) => format!("{} ml", amount(volume.get::<milliliter>())),
+            Quantity::Nominal(value, unit) => format!("{} {}", amount(*value), unit),
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Quantity, ParseError> {
+        parse::parse_quantity(text)
+    }
+}
+
+/// Serialize a quantity in its compact string form.
+pub fn serialize<S>(quantity: &Quantity, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&quantity.compact())
+}
+
+/// Deserialize a quantity from its compact string form.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Quantity, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+    text.parse()
+        .map_err(|e: ParseError| de::Error::custom(e.render()))
+}
@@ -0,0 +1,81 @@
+//! This module relates the [`Quantity`] variants to one another for a specific food. FDC weighs
+//! the household measures of most foods in their [`FoodPortion`] records, so a portion measured
+//! by volume tells us the density of the food, and with it the mass of any volume of it.
+
+use super::{parse, Quantity};
+use crate::fdc::{FDCMeta, FoodPortion};
+
+use uom::si::f32::{Mass, MassDensity};
+use uom::si::mass::gram;
+
+/// The ways a quantity can fail to convert to a mass.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ConversionError {
+    #[error("the food has no portion measured by volume to derive a density from")]
+    NoDensity,
+    #[error("the nominal unit {0:?} does not match a portion of the food")]
+    Nominal(String),
+}
+
+/// The portion as a quantity, if its unit is one we can parse. FDC names the unit of a portion
+/// in its measure unit, or for older foods in its modifier or description.
+pub fn portion_quantity(portion: &FoodPortion) -> Option<Quantity> {
+    let amount = portion.amount.unwrap_or(1.0);
+    let unit = portion
+        .measure_unit
+        .as_ref()
+        .map(|unit| &unit.name[..])
+        .filter(|name| *name != "undetermined");
+    let candidates = [
+        unit.map(|unit| format!("{} {}", amount, unit)),
+        portion
+            .modifier
+            .as_ref()
+            .map(|modifier| format!("{} {}", amount, modifier)),
+        portion.portion_description.clone(),
+    ];
+    candidates
+        .iter()
+        .flatten()
+        .find_map(|text| parse::quantity(text).ok().map(|(_, quantity)| quantity))
+}
+
+/// The density of a food with these portions, from the first portion measured by volume.
+pub fn density(portions: &[FoodPortion]) -> Option<MassDensity> {
+    portions
+        .iter()
+        .filter(|portion| portion.gram_weight > 0.0)
+        .find_map(|portion| match portion_quantity(portion) {
+            Some(Quantity::Volume(volume)) if volume.value > 0.0 => {
+                Some(Mass::new::<gram>(portion.gram_weight) / volume)
+            }
+            _ => None,
+        })
+}
+
+impl Quantity {
+    /// Convert the quantity to a mass, using the density for volumes.
+    pub fn to_mass(&self, density: Option<MassDensity>) -> Result<Mass, ConversionError> {
+        match self {
+            Quantity::Mass(mass) => Ok(*mass),
+            Quantity::Volume(volume) => density
+                .map(|density| density * *volume)
+                .ok_or(ConversionError::NoDensity),
+            Quantity::Nominal(_, unit) => Err(ConversionError::Nominal(unit.clone())),
+        }
+    }
+
+    /// Convert the quantity to a mass of the food, deriving its density from its portions and
+    /// resolving nominal units against them.
+    pub fn to_mass_of(&self, food: &FDCMeta) -> Result<Mass, ConversionError> {
+        match self.resolve(food.food_portions()) {
+            Some(portion) => Ok(Mass::new::<gram>(portion.grams)),
+            None => self.to_mass(density(food.food_portions())),
+        }
+    }
+
+    /// Convert the quantity to grams of the food, the unit FDC reports nutrients against.
+    pub fn grams_of(&self, food: &FDCMeta) -> Result<f32, ConversionError> {
+        self.to_mass_of(food).map(|mass| mass.get::<gram>())
+    }
+}
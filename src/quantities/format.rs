@@ -0,0 +1,310 @@
+//! This module writes a [`Quantity`] for people to read, like `"1 ½ cups"`. A [`Formatter`]
+//! writes in a [`UnitSystem`], picking the largest unit of the system the quantity makes at
+//! least one of, so that 3 teaspoons are written as 1 tablespoon.
+
+use super::Quantity;
+
+use std::fmt;
+
+use uom::si::f32::{Mass, Volume};
+use uom::si::{
+    mass::{gram, kilogram, milligram, ounce, pound},
+    volume::{cup, fluid_ounce, gallon, liter, milliliter, quart_liquid, tablespoon, teaspoon},
+};
+
+/// The share an amount may fall short of a unit and still be written in it, so that conversions
+/// like 3 teaspoons to 0.99999994 tablespoons do not change the unit.
+const TOLERANCE: f32 = 1e-3;
+
+/// The denominators kitchen fractions are written with, which all have a vulgar fraction.
+const DENOMINATORS: [u32; 5] = [1, 2, 3, 4, 8];
+
+/// The systems of units quantities can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitSystem {
+    /// metric units, like `"15 ml"`
+    Metric,
+    /// US customary units, like `"1 fl oz"`
+    UsCustomary,
+    /// the units of recipes, with fractions, like `"1 ½ cups"`
+    Kitchen,
+}
+
+/// A unit quantities are written in, along with the least amount it is written for.
+struct Unit<Q> {
+    singular: &'static str,
+    plural: &'static str,
+    least: f32,
+    amount: fn(&Q) -> f32,
+}
+
+const METRIC_VOLUMES: &[Unit<Volume>] = &[
+    Unit {
+        singular: "ml",
+        plural: "ml",
+        least: 0.0,
+        amount: |volume| volume.get::<milliliter>(),
+    },
+    Unit {
+        singular: "l",
+        plural: "l",
+        least: 1.0,
+        amount: |volume| volume.get::<liter>(),
+    },
+];
+
+const METRIC_MASSES: &[Unit<Mass>] = &[
+    Unit {
+        singular: "mg",
+        plural: "mg",
+        least: 0.0,
+        amount: |mass| mass.get::<milligram>(),
+    },
+    Unit {
+        singular: "g",
+        plural: "g",
+        least: 1.0,
+        amount: |mass| mass.get::<gram>(),
+    },
+    Unit {
+        singular: "kg",
+        plural: "kg",
+        least: 1.0,
+        amount: |mass| mass.get::<kilogram>(),
+    },
+];
+
+const US_VOLUMES: &[Unit<Volume>] = &[
+    Unit {
+        singular: "tsp",
+        plural: "tsp",
+        least: 0.0,
+        amount: |volume| volume.get::<teaspoon>(),
+    },
+    Unit {
+        singular: "tbsp",
+        plural: "tbsp",
+        least: 1.0,
+        amount: |volume| volume.get::<tablespoon>(),
+    },
+    Unit {
+        singular: "fl oz",
+        plural: "fl oz",
+        least: 1.0,
+        amount: |volume| volume.get::<fluid_ounce>(),
+    },
+    Unit {
+        singular: "cup",
+        plural: "cups",
+        least: 1.0,
+        amount: |volume| volume.get::<cup>(),
+    },
+    Unit {
+        singular: "qt",
+        plural: "qt",
+        least: 1.0,
+        amount: |volume| volume.get::<quart_liquid>(),
+    },
+    Unit {
+        singular: "gal",
+        plural: "gal",
+        least: 1.0,
+        amount: |volume| volume.get::<gallon>(),
+    },
+];
+
+/// Recipes measure in cups from a quarter cup, and never in fluid ounces.
+const KITCHEN_VOLUMES: &[Unit<Volume>] = &[
+    Unit {
+        singular: "tsp",
+        plural: "tsp",
+        least: 0.0,
+        amount: |volume| volume.get::<teaspoon>(),
+    },
+    Unit {
+        singular: "tbsp",
+        plural: "tbsp",
+        least: 1.0,
+        amount: |volume| volume.get::<tablespoon>(),
+    },
+    Unit {
+        singular: "cup",
+        plural: "cups",
+        least: 0.25,
+        amount: |volume| volume.get::<cup>(),
+    },
+];
+
+const US_MASSES: &[Unit<Mass>] = &[
+    Unit {
+        singular: "oz",
+        plural: "oz",
+        least: 0.0,
+        amount: |mass| mass.get::<ounce>(),
+    },
+    Unit {
+        singular: "lb",
+        plural: "lb",
+        least: 1.0,
+        amount: |mass| mass.get::<pound>(),
+    },
+];
+
+impl UnitSystem {
+    /// the units volumes are written in, from smallest to largest
+    fn volumes(&self) -> &'static [Unit<Volume>] {
+        match self {
+            UnitSystem::Metric => METRIC_VOLUMES,
+            UnitSystem::UsCustomary => US_VOLUMES,
+            UnitSystem::Kitchen => KITCHEN_VOLUMES,
+        }
+    }
+
+    /// the units masses are written in, from smallest to largest
+    fn masses(&self) -> &'static [Unit<Mass>] {
+        match self {
+            UnitSystem::Metric => METRIC_MASSES,
+            UnitSystem::UsCustomary | UnitSystem::Kitchen => US_MASSES,
+        }
+    }
+}
+
+/// helper function which picks the largest unit the quantity makes enough of
+fn pick<'a, Q>(units: &'a [Unit<Q>], quantity: &Q) -> (f32, &'a Unit<Q>) {
+    units
+        .iter()
+        .rev()
+        .map(|unit| ((unit.amount)(quantity), unit))
+        .find(|(amount, unit)| *amount >= unit.least * (1.0 - TOLERANCE))
+        .unwrap_or_else(|| ((units[0].amount)(quantity), &units[0]))
+}
+
+/// Writes quantities for people to read. Amounts are written with at most `precision` decimals,
+/// or in the kitchen as fractions with denominators up to `denominator`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Formatter {
+    pub system: UnitSystem,
+    pub precision: usize,
+    pub denominator: u32,
+}
+
+impl Default for Formatter {
+    fn default() -> Formatter {
+        Formatter::new(UnitSystem::Metric)
+    }
+}
+
+impl Formatter {
+    /// a formatter which writes in a unit system, with up to 2 decimals or eighths
+    pub fn new(system: UnitSystem) -> Formatter {
+        Formatter {
+            system,
+            precision: 2,
+            denominator: 8,
+        }
+    }
+
+    /// the formatter, but writing amounts with at most this many decimals
+    pub fn with_precision(self, precision: usize) -> Formatter {
+        Formatter { precision, ..self }
+    }
+
+    /// the formatter, but writing kitchen fractions with denominators up to this one
+    pub fn with_denominator(self, denominator: u32) -> Formatter {
+        Formatter {
+            denominator,
+            ..self
+        }
+    }
+
+    /// Write the quantity in the unit system, like `"1 ½ cups"`.
+    pub fn format(&self, quantity: &Quantity) -> String {
+        match quantity {
+            Quantity::Volume(volume) => self.format_unit(pick(self.system.volumes(), volume)),
+            Quantity::Mass(mass) => self.format_unit(pick(self.system.masses(), mass)),
+            Quantity::Nominal(amount, unit) => format!("{} {}", self.amount(*amount), unit),
+        }
+    }
+
+    /// helper function which writes an amount of a unit, in the plural when there is more than
+    /// one of it
+    fn format_unit<Q>(&self, (amount, unit): (f32, &Unit<Q>)) -> String {
+        let name = if amount > 1.0 + TOLERANCE {
+            unit.plural
+        } else {
+            unit.singular
+        };
+        format!("{} {}", self.amount(amount), name)
+    }
+
+    /// Write an amount, as a fraction in the kitchen and as a decimal otherwise.
+    pub fn amount(&self, amount: f32) -> String {
+        match self.system {
+            UnitSystem::Kitchen => fraction(amount, self.denominator)
+                .unwrap_or_else(|| decimal(amount, self.precision)),
+            _ => decimal(amount, self.precision),
+        }
+    }
+}
+
+/// helper function which writes an amount with at most this many decimals
+fn decimal(amount: f32, precision: usize) -> String {
+    let text = format!("{:.*}", precision, amount);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').into()
+    } else {
+        text
+    }
+}
+
+/// The amount as a whole number and a vulgar fraction, like `"1 ½"`, rounded to the closest
+/// fraction with a denominator up to `max_denominator`. Amounts which round to nothing have no
+/// fraction.
+fn fraction(amount: f32, max_denominator: u32) -> Option<String> {
+    let (numerator, denominator) = DENOMINATORS
+        .iter()
+        .filter(|denominator| **denominator <= max_denominator.max(1))
+        .map(|denominator| ((amount * *denominator as f32).round() as u32, *denominator))
+        .fold(None, |best: Option<(u32, u32)>, (n, d)| {
+            let error = |(n, d): (u32, u32)| (amount - n as f32 / d as f32).abs();
+            match best {
+                Some(best) if error(best) <= error((n, d)) + f32::EPSILON => Some(best),
+                _ => Some((n, d)),
+            }
+        })?;
+    if numerator == 0 {
+        return None;
+    }
+    let whole = numerator / denominator;
+    let vulgar = match (numerator % denominator, denominator) {
+        (0, _) => None,
+        (1, 2) => Some('½'),
+        (1, 3) => Some('⅓'),
+        (2, 3) => Some('⅔'),
+        (1, 4) => Some('¼'),
+        (3, 4) => Some('¾'),
+        (1, 8) => Some('⅛'),
+        (3, 8) => Some('⅜'),
+        (5, 8) => Some('⅝'),
+        (7, 8) => Some('⅞'),
+        // even numerators were written with a smaller denominator
+        _ => None,
+    };
+    Some(match (whole, vulgar) {
+        (_, None) => whole.to_string(),
+        (0, Some(vulgar)) => vulgar.to_string(),
+        (_, Some(vulgar)) => format!("{} {}", whole, vulgar),
+    })
+}
+
+/// Quantities display in metric units, with the precision of the format if it has one, like
+/// `format!("{:.1}", quantity)`.
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let formatter = match f.precision() {
+            Some(precision) => Formatter::default().with_precision(precision),
+            None => Formatter::default(),
+        };
+        f.write_str(&formatter.format(self))
+    }
+}
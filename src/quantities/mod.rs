@@ -1,17 +1,67 @@
 //! This module declares the [`Quantity`] type to type different servings a food might have, along
-//! with its associated string parsers.
+//! with its associated string parsers and its conversions for a specific food.
 
+pub mod arithmetic;
+pub mod compact;
+pub mod convert;
+pub mod format;
 pub mod parse;
+pub mod resolve;
+pub mod serving;
 
 use uom::si::f32::{Mass, Volume};
 
-/// Serving quantities are either measured in volume/mass SI units or nominally.
-#[derive(Debug, PartialEq)]
+/// Serving quantities are either measured in volume/mass SI units or nominally. They serialize
+/// with their volumes and masses in the SI base units, so that they read back exactly; see
+/// [`compact`] for a human readable form.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Quantity {
     Volume(Volume),
     Mass(Mass),
     Nominal(f32, String),
 }
 
+/// A quantity as it was written, which may be a range like `"2-3 cups"` or marked approximate
+/// like `"about 4 oz"`. The ends of a range always share a unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    /// the quantity, or the lower end of the range
+    pub low: Quantity,
+    /// the upper end of the range, if the quantity is a range
+    pub high: Option<Quantity>,
+    /// whether the quantity was written as approximate
+    pub approximate: bool,
+}
+
+impl Estimate {
+    /// whether the estimate is a range of quantities
+    pub fn is_range(&self) -> bool {
+        self.high.is_some()
+    }
+
+    /// The quantity halfway between the ends of the range, or the quantity itself when the
+    /// estimate is not a range.
+    pub fn midpoint(&self) -> Quantity {
+        match &self.high {
+            Some(high) => match self.low.checked_add(high) {
+                Ok(sum) => sum * 0.5,
+                Err(_) => self.low.clone(),
+            },
+            None => self.low.clone(),
+        }
+    }
+}
+
+impl From<Quantity> for Estimate {
+    fn from(quantity: Quantity) -> Estimate {
+        Estimate {
+            low: quantity,
+            high: None,
+            approximate: false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test;
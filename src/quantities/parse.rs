@@ -1,22 +1,26 @@
 //! This module provides the parsing functionality for serving quantities.
 
-use super::Quantity;
+use super::{Estimate, Quantity};
+
+use std::fmt;
+use std::ops::Range;
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
-use nom::character::complete::{char, digit1, multispace0, multispace1};
-use nom::character::is_alphabetic;
-use nom::combinator::{eof, iterator, map_opt, opt};
+use nom::character::complete::{alpha1, anychar, digit1, multispace0, multispace1, one_of};
+use nom::combinator::{iterator, map, map_opt, opt};
 use nom::error::{Error, ErrorKind};
+use nom::multi::separated_list1;
 use nom::number::complete::float;
 use nom::sequence::{delimited, preceded, terminated, tuple};
-use nom::{Err, IResult, Parser};
+use nom::{Err, IResult};
 
-/// Parse a fraction string like `"1/2"` to the corresponding float.
+/// Parse a fraction string like `"1/2"`, or `"1⁄2"` with a fraction slash, to the
+/// corresponding float.
 fn fraction(input: &str) -> IResult<&str, f32> {
     let digit_pair = tuple((
         digit1,
-        delimited(multispace0, char('/'), multispace0),
+        delimited(multispace0, one_of("/\u{2044}\u{2215}"), multispace0),
         terminated(digit1, multispace0),
     ));
     map_opt(digit_pair, |(str0, _, str1): (&str, char, &str)| {
@@ -26,32 +30,160 @@ fn fraction(input: &str) -> IResult<&str, f32> {
     })(input)
 }
 
-/// Parse a compound fraction string like `"1 1/2"` to the corresponding float.
+/// Parse a compound fraction string like `"1 1/2"`, or `"1-1/2"` as labels often write them, to
+/// the corresponding float.
 fn compound_fraction(input: &str) -> IResult<&str, f32> {
     map_opt(
-        tuple((digit1, multispace1, fraction)),
+        tuple((digit1, alt((multispace1, tag("-"))), fraction)),
+        |(whole, _, frac): (&str, &str, f32)| whole.parse::<f32>().ok().map(|n| n + frac),
+    )(input)
+}
+
+/// Parse a Unicode vulgar fraction like `"½"` to the corresponding float.
+fn vulgar_fraction(input: &str) -> IResult<&str, f32> {
+    map_opt(anychar, |c| match c {
+        '\u{bd}' => Some(1.0 / 2.0),
+        '\u{2153}' => Some(1.0 / 3.0),
+        '\u{2154}' => Some(2.0 / 3.0),
+        '\u{bc}' => Some(1.0 / 4.0),
+        '\u{be}' => Some(3.0 / 4.0),
+        '\u{2155}' => Some(1.0 / 5.0),
+        '\u{2156}' => Some(2.0 / 5.0),
+        '\u{2157}' => Some(3.0 / 5.0),
+        '\u{2158}' => Some(4.0 / 5.0),
+        '\u{2159}' => Some(1.0 / 6.0),
+        '\u{215a}' => Some(5.0 / 6.0),
+        '\u{2150}' => Some(1.0 / 7.0),
+        '\u{215b}' => Some(1.0 / 8.0),
+        '\u{215c}' => Some(3.0 / 8.0),
+        '\u{215d}' => Some(5.0 / 8.0),
+        '\u{215e}' => Some(7.0 / 8.0),
+        '\u{2151}' => Some(1.0 / 9.0),
+        '\u{2152}' => Some(1.0 / 10.0),
+        '\u{2189}' => Some(0.0),
+        _ => None,
+    })(input)
+}
+
+/// Parse a mixed number with a vulgar fraction like `"1½"` or `"1 ½"` to the corresponding
+/// float.
+fn compound_vulgar_fraction(input: &str) -> IResult<&str, f32> {
+    map_opt(
+        tuple((digit1, multispace0, vulgar_fraction)),
         |(whole, _, frac): (&str, &str, f32)| whole.parse::<f32>().ok().map(|n| n + frac),
     )(input)
 }
 
-/// Parse any numeric string like `"3/2"`, `"1 1/2"`, or `"1.5"` to the corresponding float.
+/// The value of a written-out number like `"seven"`, for the numbers below twenty.
+fn ones_value(word: &str) -> Option<f32> {
+    let value = match &word.to_lowercase()[..] {
+        "zero" => 0.0,
+        "one" => 1.0,
+        "two" => 2.0,
+        "three" => 3.0,
+        "four" => 4.0,
+        "five" => 5.0,
+        "six" => 6.0,
+        "seven" => 7.0,
+        "eight" => 8.0,
+        "nine" => 9.0,
+        "ten" => 10.0,
+        "eleven" => 11.0,
+        "twelve" => 12.0,
+        "thirteen" => 13.0,
+        "fourteen" => 14.0,
+        "fifteen" => 15.0,
+        "sixteen" => 16.0,
+        "seventeen" => 17.0,
+        "eighteen" => 18.0,
+        "nineteen" => 19.0,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// The value of a written-out multiple of ten like `"forty"`.
+fn tens_value(word: &str) -> Option<f32> {
+    let value = match &word.to_lowercase()[..] {
+        "twenty" => 20.0,
+        "thirty" => 30.0,
+        "forty" => 40.0,
+        "fifty" => 50.0,
+        "sixty" => 60.0,
+        "seventy" => 70.0,
+        "eighty" => 80.0,
+        "ninety" => 90.0,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// The value of a word which counts or divides, like `"a"`, `"half"` or `"dozen"`.
+fn count_value(word: &str) -> Option<f32> {
+    let value = match &word.to_lowercase()[..] {
+        "a" | "an" => 1.0,
+        "half" | "halves" => 1.0 / 2.0,
+        "third" | "thirds" => 1.0 / 3.0,
+        "quarter" | "quarters" => 1.0 / 4.0,
+        "dozen" | "dozens" => 12.0,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Parse a single number word like `"two"`, `"twenty-one"`, `"a"` or `"dozen"` to the
+/// corresponding float. Whole words are matched, so that `"a"` does not match `"apple"`.
+fn number_word(input: &str) -> IResult<&str, f32> {
+    let compound = map_opt(
+        tuple((alpha1, alt((tag("-"), multispace1)), alpha1)),
+        |(tens, _, ones): (&str, &str, &str)| {
+            Some(tens_value(tens)? + ones_value(ones).filter(|ones| *ones < 10.0)?)
+        },
+    );
+    let single = map_opt(alpha1, |word: &str| {
+        ones_value(word)
+            .or_else(|| tens_value(word))
+            .or_else(|| count_value(word))
+    });
+    alt((compound, single))(input)
+}
+
+/// Parse written-out numbers like `"two"`, `"half a"`, `"a dozen"` or `"one and a half"` to the
+/// corresponding float. Consecutive number words multiply, so `"half a dozen"` is six, and
+/// numbers joined by `"and"` add.
+fn number_words(input: &str) -> IResult<&str, f32> {
+    let product = map(separated_list1(multispace1, number_word), |factors| {
+        factors.iter().product::<f32>()
+    });
+    let and = tuple((multispace1, tag_no_case("and"), multispace1));
+    map(separated_list1(and, product), |terms| terms.iter().sum())(input)
+}
+
+/// Parse any numeric string like `"3/2"`, `"1 1/2"`, `"1½"`, `"1.5"` or `"one and a half"` to
+/// the corresponding float.
 pub fn number(input: &str) -> IResult<&str, f32> {
-    alt((compound_fraction, fraction, float))(input)
+    alt((
+        compound_fraction,
+        compound_vulgar_fraction,
+        fraction,
+        vulgar_fraction,
+        float,
+        number_words,
+    ))(input)
 }
 
 /// This is a simple parser that allows for words to have inter-hyphens and terminating
 /// periods, as is usually the case with unit names.
-pub fn unit_word<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
-    let opt_split_index = input.as_bytes().iter().enumerate().find_map(|(i, b)| {
-        let c = char::from(*b);
-        if is_alphabetic(*b) || ((c == '.' || c == '-') && i != 0) {
+pub fn unit_word(input: &str) -> IResult<&str, &str> {
+    let opt_split_index = input.char_indices().find_map(|(i, c)| {
+        if c.is_alphabetic() || ((c == '.' || c == '-') && i != 0) {
             None
         } else {
             Some(i)
         }
     });
     match opt_split_index {
-        Some(i) if i == 0 => Err(Err::Error(Error::new(input, ErrorKind::Alpha))),
+        Some(0) => Err(Err::Error(Error::new(input, ErrorKind::Alpha))),
         Some(i) => Ok((&input[i..], &input[..i])),
         None => Ok(("", input)),
     }
@@ -60,22 +192,27 @@ pub fn unit_word<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
 /// Parser for a food quantity. It is achieved by first matching on a numeric value and
 /// iteratively grabbing words until the resulting string matches an SI unit or it can grab no
 /// more. In the latter case, it returns the [`Quantity::Nominal`] variant.
-pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
+pub fn quantity(input: &str) -> IResult<&str, Quantity> {
+    let (input, val) = terminated(number, multispace0)(input)?;
+    unit(val, input)
+}
+
+/// helper function which parses the unit following a numeric value into a quantity
+fn unit(val: f32, input: &str) -> IResult<&str, Quantity> {
     // any quantity must be a number and at least one word
-    let number_space = terminated(number, multispace0);
-    let mut required = tuple((number_space, unit_word));
-    match required.parse(input) {
+    match unit_word(input) {
         // if we cannot match "number word", then we consider the parser failed
         Err(e) => Err(e),
         // otherwise, we check if "word" is associated to some si unit
-        Ok((input, (val, word))) => match units::si_quantity(val.clone(), word) {
-            // if so, return the quantity
-            Some(quantity) => Ok((input, quantity)),
+        Ok((input, word)) => match units::si_quantity(val, word) {
+            // if so, return the quantity, unless the words which follow spell a longer unit
+            Some(quantity) => Ok(longest_unit(val, input, word, quantity)),
             // if not, continue grabbing words
             None => {
-                // a string buffer will hold the words we see
+                // a string buffer will hold the words we see, in their case so that units
+                // like "T" and "t" can be told apart
                 let mut words = String::with_capacity(256);
-                words.push_str(&word.to_lowercase());
+                words.push_str(word);
 
                 // create an iterator which will repeatedly grab words, checking if the
                 // sequence of words is associated to an SI unit. If we run out of words before
@@ -83,12 +220,12 @@ pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
                 let mut iter = iterator(input, preceded(multispace1, unit_word));
                 let quantity = iter
                     .scan(&mut words, |words, word| {
-                        words.push_str(" ");
-                        words.push_str(&word.to_lowercase());
-                        Some(units::si_quantity(val.clone(), words))
+                        words.push(' ');
+                        words.push_str(word);
+                        Some(units::si_quantity(val, words))
                     })
                     .find_map(|opt_quant| opt_quant)
-                    .unwrap_or(Quantity::Nominal(val, words));
+                    .unwrap_or_else(|| Quantity::Nominal(val, words.to_lowercase()));
                 let (input, _) = iter.finish()?;
                 Ok((input, quantity))
             }
@@ -96,19 +233,93 @@ pub fn quantity<'a>(input: &'a str) -> IResult<&'a str, Quantity> {
     }
 }
 
-pub fn noise<'a>(input: &'a str) -> IResult<&'a str, ()> {
-    let mut iter = iterator::<&'a str, &'a str, Error<&'a str>, _>(
-        input,
-        alt((
-            tag_no_case("about"),
-            tag_no_case("approx."),
-            tag_no_case("approximately"),
-            tag_no_case("makes"),
-            tag("\""),
-            tag("|"),
-            multispace1,
-        )),
-    );
+/// helper function which extends a unit over the words which follow it for as long as they could
+/// still spell a longer unit, so that `"cup dry"` is read as dry cups rather than as cups
+fn longest_unit<'a>(
+    val: f32,
+    input: &'a str,
+    word: &str,
+    quantity: Quantity,
+) -> (&'a str, Quantity) {
+    let mut words = word.to_string();
+    let mut longest = (input, quantity);
+    let mut rest = input;
+    while units::starts_unit(&words) {
+        let (next, word) = match preceded(multispace1, unit_word)(rest) {
+            Ok(next) => next,
+            Err(_) => break,
+        };
+        words.push(' ');
+        words.push_str(word);
+        rest = next;
+        if let Some(quantity) = units::si_quantity(val, &words) {
+            longest = (rest, quantity);
+        }
+    }
+    longest
+}
+
+/// Parse a word like `"about"` which marks a quantity as approximate.
+fn approximation(input: &str) -> IResult<&str, &str> {
+    alt((
+        tag_no_case("approximately"),
+        tag_no_case("approx."),
+        tag_no_case("approx"),
+        tag_no_case("about"),
+        tag_no_case("around"),
+        tag_no_case("roughly"),
+        tag("~"),
+    ))(input)
+}
+
+/// Parse the separator between the ends of a range, like the `"-"` in `"2-3"`.
+fn range_separator(input: &str) -> IResult<&str, &str> {
+    delimited(
+        multispace0,
+        alt((tag("-"), tag("\u{2013}"), tag_no_case("to"))),
+        multispace0,
+    )(input)
+}
+
+/// Parser for a food quantity which may be a range like `"2-3 cups"` or `"1 to 2 tbsp"`, or
+/// approximate like `"about 4 oz"`. The unit is parsed once and shared by both ends of a range,
+/// whose high end may not be below its low end.
+pub fn estimate(input: &str) -> IResult<&str, Estimate> {
+    let (input, approximate) = opt(terminated(approximation, multispace0))(input)?;
+    let start = input;
+    let (input, (low, high)) = terminated(
+        tuple((number, opt(preceded(range_separator, number)))),
+        multispace0,
+    )(input)?;
+    if high.map(|high| high < low).unwrap_or(false) {
+        return Err(Err::Error(Error::new(start, ErrorKind::Verify)));
+    }
+    let (rest, low) = unit(low, input)?;
+    let high = match high {
+        Some(high) => Some(unit(high, input)?.1),
+        None => None,
+    };
+    Ok((
+        rest,
+        Estimate {
+            low,
+            high,
+            approximate: approximate.is_some(),
+        },
+    ))
+}
+
+/// Parse the artifacts of label text which carry no meaning, like quotes and pipes.
+fn artifact(input: &str) -> IResult<&str, &str> {
+    alt((tag_no_case("makes"), tag("\""), tag("|"), multispace1))(input)
+}
+
+/// helper function which skips every match of a parser
+fn skip_all<'a, P>(input: &'a str, parser: P) -> IResult<&'a str, ()>
+where
+    P: FnMut(&'a str) -> IResult<&'a str, &'a str>,
+{
+    let mut iter = iterator(input, parser);
     iter.for_each(|_| {});
     match iter.finish() {
         Ok(o) => Ok(o),
@@ -116,102 +327,240 @@ pub fn noise<'a>(input: &'a str) -> IResult<&'a str, ()> {
     }
 }
 
-/// Parser for the food quantities on a label. Implemented by stripping artifacts and repeatedly
-/// applying the [`quantity`] parser.
-pub fn quantities<'a>(input: &'a str) -> IResult<&'a str, Vec<Quantity>> {
-    // first run a parse on a single quantity
-    let res = delimited(noise, quantity, multispace0)(input);
-    match res {
-        Err(e) => Err(e),
-        Ok((input, q)) => {
-            // if we saw a quantity, continue to parse until eof, allowing parentheses
-            let mut iter = iterator(
-                input,
-                delimited(
-                    multispace0,
-                    delimited(
-                        opt(tag("(")),
-                        delimited(noise, quantity, noise),
-                        opt(tag(")")),
-                    ),
-                    multispace0,
-                ),
-            );
-            let mut quants = iter.collect::<Vec<_>>();
-            quants.push(q);
-            let (input, _) = iter.finish()?;
-            let _ = preceded(noise, eof)(input)?;
-            Ok((input, quants))
+/// Skip the artifacts of label text, but not the words marking a quantity approximate.
+fn artifacts(input: &str) -> IResult<&str, ()> {
+    skip_all(input, artifact)
+}
+
+/// Skip the artifacts of label text along with the words marking a quantity approximate.
+pub fn noise(input: &str) -> IResult<&str, ()> {
+    skip_all(input, alt((approximation, artifact)))
+}
+
+/// Parser for the food quantities on a label, as written. Implemented by stripping artifacts and
+/// repeatedly applying the [`estimate`] parser, allowing estimates to be wrapped in parentheses.
+pub fn estimates(input: &str) -> IResult<&str, Vec<Estimate>> {
+    match walk(input) {
+        Ok(ests) => Ok(("", ests)),
+        Err((rest, expected)) => Err(Err::Error(Error::new(rest, expected.kind()))),
+    }
+}
+
+/// helper function which parses estimates until the end of the text, returning where and why
+/// it failed otherwise
+fn walk(input: &str) -> Result<Vec<Estimate>, (&str, Expected)> {
+    let mut ests = Vec::new();
+    let mut input = skip(artifacts, input);
+    loop {
+        // an estimate, possibly wrapped in parentheses
+        let open = input.starts_with('(');
+        let rest = skip(artifacts, if open { &input[1..] } else { input });
+        let (rest, est) = estimate(rest).map_err(|_| expectation(rest))?;
+        ests.push(est);
+        let rest = skip(noise, rest);
+        let rest = match (open, rest.strip_prefix(')')) {
+            (false, _) => rest,
+            (true, Some(rest)) => rest,
+            (true, None) => return Err((rest, Expected::ClosingParenthesis)),
+        };
+
+        // stop at the end of the text
+        if skip(noise, rest).is_empty() {
+            return Ok(ests);
+        }
+        input = skip(multispace0, rest);
+    }
+}
+
+/// helper function which runs a parser, returning the rest of the input, or all of it when the
+/// parser fails
+fn skip<'a, O>(mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>, input: &'a str) -> &'a str {
+    parser(input).map(|(rest, _)| rest).unwrap_or(input)
+}
+
+/// helper function which decides what an estimate failed on, and where
+fn expectation(input: &str) -> (&str, Expected) {
+    let input = skip(opt(terminated(approximation, multispace0)), input);
+    let numbers = terminated(
+        tuple((number, opt(preceded(range_separator, number)))),
+        multispace0,
+    );
+    match skip(numbers, input) {
+        rest if rest.len() == input.len() => (input, Expected::Number),
+        rest => (rest, Expected::Unit),
+    }
+}
+
+/// Parser for the food quantities on a label, taking the midpoint of any range. Implemented with
+/// the [`estimates`] parser.
+pub fn quantities(input: &str) -> IResult<&str, Vec<Quantity>> {
+    map(estimates, |ests| {
+        ests.iter().map(Estimate::midpoint).collect()
+    })(input)
+}
+
+/// What the parser expected where it failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Number,
+    Unit,
+    MassOrVolume,
+    ClosingParenthesis,
+    End,
+}
+
+impl Expected {
+    /// the nom error kind closest to the expectation
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Expected::Number => ErrorKind::Digit,
+            Expected::Unit => ErrorKind::Alpha,
+            Expected::MassOrVolume => ErrorKind::Verify,
+            Expected::ClosingParenthesis => ErrorKind::Char,
+            Expected::End => ErrorKind::Eof,
+        }
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Expected::Number => "a number",
+            Expected::Unit => "a unit",
+            Expected::MassOrVolume => "a unit of mass or volume",
+            Expected::ClosingParenthesis => "a closing parenthesis",
+            Expected::End => "the end of the text",
+        })
+    }
+}
+
+/// A failure to parse quantity text, locating the failure by its byte span in the text.
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("expected {expected}, found {}", self.found())]
+pub struct ParseError {
+    pub text: String,
+    pub span: Range<usize>,
+    pub expected: Expected,
+    /// what the text may have meant, like a known unit close to a misspelled one
+    pub suggestion: Option<String>,
+}
+
+impl ParseError {
+    /// The error at the rest of the text, spanning the word found there, or the single
+    /// character found there when it is a space or parenthesis.
+    fn at(text: &str, rest: &str, expected: Expected) -> ParseError {
+        let start = text.len() - rest.len();
+        let first = rest.chars().next().map(char::len_utf8).unwrap_or(0);
+        let word = rest
+            .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+            .map(|end| if end == 0 { first } else { end })
+            .unwrap_or(rest.len());
+        ParseError {
+            text: text.into(),
+            span: start..start + word,
+            expected,
+            suggestion: None,
+        }
+    }
+
+    /// the text the parser failed on
+    pub fn found(&self) -> String {
+        match &self.text[self.span.clone()] {
+            "" => "the end of the text".into(),
+            found => format!("{:?}", found),
+        }
+    }
+
+    /// Render the error with the text and a caret marking the span, like
+    ///
+    /// ```text
+    /// expected a unit of mass or volume, found "grms"
+    ///   28 grms
+    ///      ^^^^
+    ///   did you mean "grams"?
+    /// ```
+    pub fn render(&self) -> String {
+        let column = self.text[..self.span.start].chars().count();
+        let width = self.text[self.span.clone()].chars().count().max(1);
+        let mut message = format!(
+            "{}\n  {}\n  {}{}",
+            self,
+            self.text,
+            " ".repeat(column),
+            "^".repeat(width)
+        );
+        if let Some(suggestion) = &self.suggestion {
+            message.push_str(&format!("\n  did you mean {:?}?", suggestion));
+        }
+        message
+    }
+}
+
+/// Parse the food quantities on a label, as written, explaining any failure.
+pub fn parse_estimates(text: &str) -> Result<Vec<Estimate>, ParseError> {
+    walk(text).map_err(|(rest, expected)| ParseError::at(text, rest, expected))
+}
+
+/// Parse the food quantities on a label, taking the midpoint of any range, explaining any
+/// failure.
+pub fn parse_quantities(text: &str) -> Result<Vec<Quantity>, ParseError> {
+    parse_estimates(text).map(|ests| ests.iter().map(Estimate::midpoint).collect())
+}
+
+/// Parse text which is exactly one quantity, like `"1.5 cup"`.
+pub fn parse_quantity(text: &str) -> Result<Quantity, ParseError> {
+    let input = skip(multispace0, text);
+    let (rest, quantity) = quantity(input).map_err(|_| {
+        let (rest, expected) = expectation(input);
+        ParseError::at(text, rest, expected)
+    })?;
+    match skip(multispace0, rest) {
+        "" => Ok(quantity),
+        rest => Err(ParseError::at(text, rest, Expected::End)),
+    }
+}
+
+/// Parse text which is exactly one quantity of mass or volume, like a serving size of
+/// `"28 g"`, suggesting a known unit when the unit is misspelled.
+pub fn parse_measure(text: &str) -> Result<Quantity, ParseError> {
+    match parse_quantity(text)? {
+        Quantity::Nominal(_, unit) => {
+            let input = skip(multispace0, text);
+            let unit_start = skip(terminated(number, multispace0), input);
+            let mut error = ParseError::at(text, unit_start, Expected::MassOrVolume);
+            error.span.end = text.trim_end().len();
+            error.suggestion = units::suggest(&unit).map(String::from);
+            Err(error)
         }
+        quantity => Ok(quantity),
     }
 }
 
-/// This module simply holds static variables which are used for parsing units
+/// This module holds the registry of units the parser knows. Each unit is a row of the
+/// [`UNITS`](units::UNITS) table, so supporting a new unit or alias is a matter of adding to it.
 mod units {
     use super::Quantity;
+    use crate::quantities::resolve::edit_distance;
     use uom::si::{
         f32::{Mass, Volume},
-        mass::{self, centigram, gram, kilogram, milligram, ounce, pound},
+        mass::{self, centigram, gram, kilogram, microgram, milligram, ounce, pound},
         volume::{
-            self, centiliter, cubic_centimeter, cubic_inch, cup, fluid_ounce, gallon, liter,
-            milliliter, pint_liquid, quart_liquid, tablespoon, teaspoon,
+            self, centiliter, cubic_centimeter, cubic_inch, cup, deciliter, fluid_ounce,
+            fluid_ounce_imperial, gallon, gallon_imperial, liter, milliliter, pint_dry,
+            pint_liquid, quart_liquid, tablespoon, teaspoon,
         },
     };
 
-    /// We store all the units in an enum to ensure each one is matched against
-    #[allow(non_camel_case_types)]
-    enum Units {
-        CENTILITER,
-        CUBIC_CENTIMETER,
-        CUBIC_INCH,
-        CUP,
-        FLUID_OUNCE,
-        GALLON,
-        LITER,
-        MILLILITER,
-        PINT,
-        QUART,
-        TABLESPOON,
-        TEASPOON,
-        CENTIGRAM,
-        GRAM,
-        KILOGRAM,
-        MILLIGRAM,
-        OUNCE,
-        POUND,
-        NONE,
-    }
-
-    /// Map various names associated to a unit to a normalized static candidate. A [`None`]
-    /// variant corresponds to the input string slice not being associated to a unit.
-    fn normalize_unit<'a>(input: &'a str) -> Units {
-        match &input.to_lowercase()[..] {
-            // volumes
-            "centiliter" | "centiliters" | "cl" => Units::CENTILITER,
-            "cubic centimeter" | "cubic centimeters" => Units::CUBIC_CENTIMETER,
-            "cubic inch" | "cubic inches" => Units::CUBIC_INCH,
-            "cup" | "cups" => Units::CUP,
-            "fl.oz." | "fl. oz." | "fl oz" | "fluid ounce" | "fluid oz" | "fluid ounces"
-            | "oza" => Units::FLUID_OUNCE,
-            "gallon" | "gallons" | "gals" | "gal" => Units::GALLON,
-            "l" | "liter" | "liters" => Units::LITER,
-            "ml" | "milliliter" | "milliliters" => Units::MILLILITER,
-            "pint" | "pints" => Units::PINT,
-            "quart" | "quarts" => Units::QUART,
-            "tbsp" | "tablespoon" | "tablespoons" => Units::TABLESPOON,
-            "tsp" | "teaspoon" | "teaspoons" => Units::TEASPOON,
-            // masses
-            "centigram" | "centigrams" | "cg" => Units::CENTIGRAM,
-            "gram" | "grams" | "g" | "grm" | "gr" => Units::GRAM,
-            "kilogram" | "kilograms" | "kg" => Units::KILOGRAM,
-            "milligram" | "milligrams" | "mg" => Units::MILLIGRAM,
-            "ounce" | "onz" | "ounces" | "oz" | "oz." | "wt. oz." | "wt.oz." | "wt oz" => {
-                Units::OUNCE
-            }
-            "pound" | "pounds" | "lb" | "lbs" => Units::POUND,
-            // no match
-            &_ => Units::NONE,
-        }
+    /// A unit the parser knows, along with the names it goes by.
+    pub struct Unit {
+        /// the name suggested for misspellings of the unit
+        pub name: &'static str,
+        /// names matched regardless of case
+        pub aliases: &'static [&'static str],
+        /// names matched only in this case, like `"T"` for tablespoons and `"t"` for teaspoons
+        pub exact: &'static [&'static str],
+        /// the quantity of an amount of the unit
+        pub quantity: fn(f32) -> Quantity,
     }
 
     /// helper function which creates volume quantities
@@ -230,35 +579,268 @@ mod units {
         Quantity::Mass(Mass::new::<U>(amount))
     }
 
-    impl Units {
-        /// helper function which creates quantities
-        fn si_quantity(&self, amount: f32) -> Option<Quantity> {
-            match self {
-                Units::CENTILITER => Some(v::<centiliter>(amount)),
-                Units::CUBIC_CENTIMETER => Some(v::<cubic_centimeter>(amount)),
-                Units::CUBIC_INCH => Some(v::<cubic_inch>(amount)),
-                Units::CUP => Some(v::<cup>(amount)),
-                Units::FLUID_OUNCE => Some(v::<fluid_ounce>(amount)),
-                Units::GALLON => Some(v::<gallon>(amount)),
-                Units::LITER => Some(v::<liter>(amount)),
-                Units::MILLILITER => Some(v::<milliliter>(amount)),
-                Units::PINT => Some(v::<pint_liquid>(amount)),
-                Units::QUART => Some(v::<quart_liquid>(amount)),
-                Units::TABLESPOON => Some(v::<tablespoon>(amount)),
-                Units::TEASPOON => Some(v::<teaspoon>(amount)),
-                Units::CENTIGRAM => Some(m::<centigram>(amount)),
-                Units::GRAM => Some(m::<gram>(amount)),
-                Units::KILOGRAM => Some(m::<kilogram>(amount)),
-                Units::MILLIGRAM => Some(m::<milligram>(amount)),
-                Units::OUNCE => Some(m::<ounce>(amount)),
-                Units::POUND => Some(m::<pound>(amount)),
-                Units::NONE => None,
-            }
-        }
+    /// The units the parser knows. Names are compared after collapsing whitespace, so aliases
+    /// are written with single spaces.
+    pub static UNITS: &[Unit] = &[
+        // volumes
+        Unit {
+            name: "centiliters",
+            aliases: &["centiliter", "centiliters", "cl"],
+            exact: &[],
+            quantity: v::<centiliter>,
+        },
+        Unit {
+            name: "deciliters",
+            aliases: &["deciliter", "deciliters", "dl"],
+            exact: &[],
+            quantity: v::<deciliter>,
+        },
+        Unit {
+            name: "cubic centimeters",
+            aliases: &["cubic centimeter", "cubic centimeters", "cc", "cm3"],
+            exact: &[],
+            quantity: v::<cubic_centimeter>,
+        },
+        Unit {
+            name: "cubic inches",
+            aliases: &["cubic inch", "cubic inches"],
+            exact: &[],
+            quantity: v::<cubic_inch>,
+        },
+        Unit {
+            name: "cups",
+            aliases: &["cup", "cups", "c", "c."],
+            exact: &[],
+            quantity: v::<cup>,
+        },
+        Unit {
+            name: "dry cups",
+            aliases: &["dry cup", "dry cups", "cup dry", "cups dry"],
+            exact: &[],
+            quantity: |amount| v::<pint_dry>(amount / 2.0),
+        },
+        Unit {
+            name: "fluid ounces",
+            aliases: &[
+                "fl.oz.",
+                "fl.oz",
+                "fl. oz.",
+                "fl. oz",
+                "fl oz",
+                "fl oz.",
+                "fluid ounce",
+                "fluid oz",
+                "fluid ounces",
+                "oza",
+            ],
+            exact: &[],
+            quantity: v::<fluid_ounce>,
+        },
+        Unit {
+            name: "imperial fluid ounces",
+            aliases: &[
+                "imperial fluid ounce",
+                "imperial fluid ounces",
+                "imperial fl oz",
+            ],
+            exact: &[],
+            quantity: v::<fluid_ounce_imperial>,
+        },
+        Unit {
+            name: "gallons",
+            aliases: &["gallon", "gallons", "gals", "gal"],
+            exact: &[],
+            quantity: v::<gallon>,
+        },
+        Unit {
+            name: "imperial gallons",
+            aliases: &["imperial gallon", "imperial gallons", "imp gal"],
+            exact: &[],
+            quantity: v::<gallon_imperial>,
+        },
+        Unit {
+            name: "liters",
+            aliases: &["l", "liter", "liters", "litre", "litres"],
+            exact: &[],
+            quantity: v::<liter>,
+        },
+        Unit {
+            name: "milliliters",
+            aliases: &[
+                "ml",
+                "mlt",
+                "milliliter",
+                "milliliters",
+                "millilitre",
+                "millilitres",
+            ],
+            exact: &[],
+            quantity: v::<milliliter>,
+        },
+        Unit {
+            name: "pints",
+            aliases: &["pint", "pints", "pt"],
+            exact: &[],
+            quantity: v::<pint_liquid>,
+        },
+        Unit {
+            name: "imperial pints",
+            aliases: &["imperial pint", "imperial pints", "imp pt"],
+            exact: &[],
+            quantity: |amount| v::<gallon_imperial>(amount / 8.0),
+        },
+        Unit {
+            name: "quarts",
+            aliases: &["quart", "quarts", "qt"],
+            exact: &[],
+            quantity: v::<quart_liquid>,
+        },
+        Unit {
+            name: "tablespoons",
+            aliases: &["tbsp", "tbsp.", "tbs", "tbs.", "tablespoon", "tablespoons"],
+            exact: &["T", "T."],
+            quantity: v::<tablespoon>,
+        },
+        Unit {
+            name: "dessertspoons",
+            aliases: &["dessertspoon", "dessertspoons", "dsp", "dstspn"],
+            exact: &[],
+            quantity: |amount| v::<milliliter>(10.0 * amount),
+        },
+        Unit {
+            name: "teaspoons",
+            aliases: &["tsp", "tsp.", "teaspoon", "teaspoons"],
+            exact: &["t", "t."],
+            quantity: v::<teaspoon>,
+        },
+        Unit {
+            name: "dashes",
+            aliases: &["dash", "dashes"],
+            exact: &[],
+            quantity: |amount| v::<teaspoon>(amount / 8.0),
+        },
+        Unit {
+            name: "pinches",
+            aliases: &["pinch", "pinches"],
+            exact: &[],
+            quantity: |amount| v::<teaspoon>(amount / 16.0),
+        },
+        Unit {
+            name: "smidgens",
+            aliases: &["smidgen", "smidgens", "smidgeon", "smidgeons"],
+            exact: &[],
+            quantity: |amount| v::<teaspoon>(amount / 32.0),
+        },
+        Unit {
+            name: "drops",
+            aliases: &["drop", "drops", "gtt"],
+            exact: &[],
+            quantity: |amount| v::<milliliter>(0.05 * amount),
+        },
+        // masses
+        Unit {
+            name: "centigrams",
+            aliases: &["centigram", "centigrams", "cg"],
+            exact: &[],
+            quantity: m::<centigram>,
+        },
+        Unit {
+            name: "grams",
+            aliases: &["gram", "grams", "g", "grm", "gr"],
+            exact: &[],
+            quantity: m::<gram>,
+        },
+        Unit {
+            name: "kilograms",
+            aliases: &["kilogram", "kilograms", "kg"],
+            exact: &[],
+            quantity: m::<kilogram>,
+        },
+        Unit {
+            name: "milligrams",
+            aliases: &["milligram", "milligrams", "mg"],
+            exact: &[],
+            quantity: m::<milligram>,
+        },
+        Unit {
+            name: "micrograms",
+            aliases: &[
+                "microgram",
+                "micrograms",
+                "mcg",
+                "ug",
+                "\u{b5}g",
+                "\u{3bc}g",
+            ],
+            exact: &[],
+            quantity: m::<microgram>,
+        },
+        Unit {
+            name: "ounces",
+            aliases: &[
+                "ounce",
+                "onz",
+                "ounces",
+                "oz",
+                "oz.",
+                "wt. oz.",
+                "wt.oz.",
+                "wt oz",
+                "net wt oz",
+            ],
+            exact: &[],
+            quantity: m::<ounce>,
+        },
+        Unit {
+            name: "pounds",
+            aliases: &["pound", "pounds", "lb", "lbs", "lb.", "lbs."],
+            exact: &[],
+            quantity: m::<pound>,
+        },
+        Unit {
+            name: "sticks of butter",
+            aliases: &["stick of butter", "sticks of butter", "stick butter"],
+            exact: &[],
+            quantity: |amount| m::<ounce>(4.0 * amount),
+        },
+    ];
+
+    /// Find the unit with a name, preferring names which match in case.
+    pub fn lookup(input: &str) -> Option<&'static Unit> {
+        let input = input.split_whitespace().collect::<Vec<_>>().join(" ");
+        let lower = input.to_lowercase();
+        UNITS
+            .iter()
+            .find(|unit| unit.exact.contains(&&input[..]))
+            .or_else(|| UNITS.iter().find(|unit| unit.aliases.contains(&&lower[..])))
+    }
+
+    /// Whether the words are the first of a longer name of some unit, like `"cup"` of
+    /// `"cup dry"`.
+    pub fn starts_unit(input: &str) -> bool {
+        let input = input.split_whitespace().collect::<Vec<_>>().join(" ") + " ";
+        let lower = input.to_lowercase();
+        UNITS.iter().any(|unit| {
+            unit.exact.iter().any(|name| name.starts_with(&input))
+                || unit.aliases.iter().any(|alias| alias.starts_with(&lower))
+        })
     }
 
     /// helper function which creates si quantities
     pub fn si_quantity(amount: f32, input: &str) -> Option<Quantity> {
-        normalize_unit(input).si_quantity(amount)
+        lookup(input).map(|unit| (unit.quantity)(amount))
+    }
+
+    /// The name of the unit closest to a misspelled one, if any is a typo or two away.
+    pub fn suggest(input: &str) -> Option<&'static str> {
+        let input = input.to_lowercase();
+        UNITS
+            .iter()
+            .flat_map(|unit| unit.aliases.iter().map(move |alias| (unit.name, *alias)))
+            .filter(|(_, alias)| alias.len() > 2)
+            .map(|(name, alias)| (name, alias, edit_distance(&input, alias)))
+            .filter(|(_, alias, distance)| *distance <= if alias.len() > 5 { 2 } else { 1 })
+            .min_by_key(|(_, _, distance)| *distance)
+            .map(|(name, _, _)| name)
     }
 }
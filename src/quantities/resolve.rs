@@ -0,0 +1,193 @@
+//! This module resolves the unit of a [`Quantity::Nominal`] like `"2 slices"` against the
+//! portions of a food like `"1 slice"`. Units and portions are compared word by word, after
+//! lowercasing and forgiving plurals, with words a typo apart still counting as a partial match.
+//! The best portion is returned along with a confidence score, which is lowered when another
+//! portion of a different weight matched nearly as well.
+
+use super::{parse, Quantity};
+use crate::fdc::FoodPortion;
+
+/// The least similarity a portion must have with a unit to resolve it.
+const MIN_SIMILARITY: f32 = 0.5;
+
+/// The similarity of words which are a typo apart.
+const TYPO_SIMILARITY: f32 = 0.8;
+
+/// A portion of a food a nominal unit resolved to.
+#[derive(Debug)]
+pub struct PortionMatch<'a> {
+    pub portion: &'a FoodPortion,
+    /// the weight of the nominal quantity, in grams
+    pub grams: f32,
+    /// how sure we are of the match, from 0 to 1
+    pub confidence: f32,
+}
+
+/// The amount and unit a portion is described by, like `(1.0, "large egg")` for the portion
+/// `"1 large egg"`. Portions whose unit is only a code, or whose amount is not positive, are left
+/// out.
+pub fn portion_label(portion: &FoodPortion) -> Option<(f32, String)> {
+    let amount = portion.amount.unwrap_or(1.0);
+    let unit = portion
+        .measure_unit
+        .as_ref()
+        .map(|unit| &unit.name[..])
+        .filter(|name| *name != "undetermined");
+    let candidates = [
+        portion
+            .portion_description
+            .as_ref()
+            .and_then(|description| match parse::number(description) {
+                Ok((rest, amount)) => Some((amount, rest.trim().to_string())),
+                Err(_) => None,
+            }),
+        portion
+            .modifier
+            .as_ref()
+            .map(|modifier| (amount, modifier.trim().to_string())),
+        unit.map(|unit| (amount, unit.to_string())),
+    ];
+    candidates
+        .iter()
+        .flatten()
+        .find(|(_, label)| label.starts_with(char::is_alphabetic))
+        .filter(|(amount, _)| *amount > 0.0)
+        .cloned()
+}
+
+/// Resolve a nominal unit against the portions of a food, if any portion is similar enough.
+pub fn resolve_portion<'a>(
+    amount: f32,
+    unit: &str,
+    portions: &'a [FoodPortion],
+) -> Option<PortionMatch<'a>> {
+    // score every labelled portion
+    let unit = words(unit);
+    let mut scores = portions
+        .iter()
+        .filter(|portion| portion.gram_weight > 0.0)
+        .filter_map(|portion| {
+            let (portion_amount, label) = portion_label(portion)?;
+            let grams_per_unit = portion.gram_weight / portion_amount;
+            Some((portion, grams_per_unit, similarity(&unit, &words(&label))))
+        })
+        .filter(|(_, _, score)| *score >= MIN_SIMILARITY)
+        .collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+    // the confidence drops with the best match of a different weight
+    let (portion, grams_per_unit, score) = *scores.first()?;
+    let runner_up = scores
+        .iter()
+        .skip(1)
+        .find(|(_, other, _)| (other - grams_per_unit).abs() > f32::EPSILON * grams_per_unit)
+        .map(|(_, _, other)| *other)
+        .unwrap_or(0.0);
+    Some(PortionMatch {
+        portion,
+        grams: amount * grams_per_unit,
+        confidence: score - runner_up / 2.0,
+    })
+}
+
+/// The similarity of two units, from 0 to 1, with the same forgiveness as portions get.
+pub fn unit_similarity(left: &str, right: &str) -> f32 {
+    similarity(&words(left), &words(right))
+}
+
+/// Whether two units are similar enough to be the same.
+pub fn same_unit(left: &str, right: &str) -> bool {
+    unit_similarity(left, right) >= MIN_SIMILARITY
+}
+
+impl Quantity {
+    /// Resolve a nominal quantity against the portions of a food. Other quantities are not
+    /// nominal, so they never resolve.
+    pub fn resolve<'a>(&self, portions: &'a [FoodPortion]) -> Option<PortionMatch<'a>> {
+        match self {
+            Quantity::Nominal(amount, unit) => resolve_portion(*amount, unit, portions),
+            _ => None,
+        }
+    }
+}
+
+/// helper function which splits text into lowercase words
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The forms a word could have in the singular, like `"loaf"` and `"loafe"` for `"loaves"`.
+/// Guessing every form is more forgiving than an inflector, which has to commit to one.
+fn singulars(word: &str) -> Vec<String> {
+    let mut forms = vec![word.to_string()];
+    let mut strip = |suffix: &str, replacements: &[&str]| {
+        if let Some(stem) = word.strip_suffix(suffix).filter(|stem| stem.len() > 1) {
+            forms.extend(
+                replacements
+                    .iter()
+                    .map(|ending| format!("{}{}", stem, ending)),
+            );
+        }
+    };
+    strip("s", &[""]);
+    strip("es", &[""]);
+    strip("ies", &["y"]);
+    strip("ves", &["f", "fe"]);
+    forms
+}
+
+/// The similarity of two lists of words, from 0 to 1, as the share of words they have in common.
+fn similarity(left: &[String], right: &[String]) -> f32 {
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    let mut unmatched = right.iter().collect::<Vec<_>>();
+    let mut matched = 0.0;
+    for word in left {
+        let best = unmatched
+            .iter()
+            .enumerate()
+            .map(|(i, other)| (i, word_similarity(word, other)))
+            .fold(None, |best: Option<(usize, f32)>, (i, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ if score > 0.0 => Some((i, score)),
+                _ => best,
+            });
+        if let Some((i, score)) = best {
+            matched += score;
+            unmatched.remove(i);
+        }
+    }
+    2.0 * matched / (left.len() + right.len()) as f32
+}
+
+/// helper function which compares words, forgiving a single typo in longer words
+fn word_similarity(left: &str, right: &str) -> f32 {
+    let rights = singulars(right);
+    if singulars(left).iter().any(|form| rights.contains(form)) {
+        1.0
+    } else if left.len().min(right.len()) >= 4 && edit_distance(left, right) <= 1 {
+        TYPO_SIMILARITY
+    } else {
+        0.0
+    }
+}
+
+/// The Levenshtein distance between two words.
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut row = (0..=right.len()).collect::<Vec<_>>();
+    for (i, l) in left.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, r) in right.iter().enumerate() {
+            let substitution = diagonal + usize::from(l != *r);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[right.len()]
+}
@@ -0,0 +1,152 @@
+//! This module connects the serving text on the label of a [`BrandedFoodItem`] to the
+//! [`parse::quantities`] parser. Labels give a household serving like `"1 cup (240 ml)"` along
+//! with a canonical serving size in grams or milliliters, which this module parses into a
+//! [`Serving`]. As label text is written by manufacturers, [`ServingReport`] collects the labels
+//! which failed to parse so that the parser can be improved against them.
+
+use super::parse::{self, ParseError};
+use super::{resolve, Quantity};
+use crate::fdc::BrandedFoodItem;
+
+use uom::si::f32::MassDensity;
+
+/// The serving of a branded food, as parsed from its label.
+#[derive(Debug, PartialEq)]
+pub struct Serving {
+    /// the quantities of the household serving, in the order of the label
+    pub household: Vec<Quantity>,
+    /// the serving size in the mass or volume FDC scales the nutrients of the food with
+    pub canonical: Quantity,
+}
+
+impl Serving {
+    /// The density of the food, when the label gives the serving both by mass and by volume.
+    pub fn density(&self) -> Option<MassDensity> {
+        let mut quantities = self.household.iter().chain(Some(&self.canonical));
+        let mass = quantities.clone().find_map(|quantity| match quantity {
+            Quantity::Mass(mass) => Some(*mass),
+            _ => None,
+        })?;
+        let volume = quantities.find_map(|quantity| match quantity {
+            Quantity::Volume(volume) => Some(*volume),
+            _ => None,
+        })?;
+        Some(mass / volume)
+    }
+
+    /// The quantity in terms of the canonical serving size, when it is a nominal quantity of the
+    /// household serving like `"3 pieces"` for a serving of `"2 pieces (28 g)"`, or of servings
+    /// themselves. Other quantities are returned as they are.
+    pub fn resolve(&self, quantity: &Quantity) -> Quantity {
+        let (amount, unit) = match quantity {
+            Quantity::Nominal(amount, unit) => (*amount, unit),
+            _ => return quantity.clone(),
+        };
+        let servings = self.household.iter().find_map(|household| match household {
+            Quantity::Nominal(household_amount, household_unit)
+                if *household_amount > 0.0 && resolve::same_unit(unit, household_unit) =>
+            {
+                Some(amount / household_amount)
+            }
+            _ => None,
+        });
+        let servings =
+            servings.or_else(|| Some(amount).filter(|_| resolve::same_unit(unit, "serving")));
+        match (servings, &self.canonical) {
+            (Some(servings), Quantity::Mass(mass)) => Quantity::Mass(*mass * servings),
+            (Some(servings), Quantity::Volume(volume)) => Quantity::Volume(*volume * servings),
+            _ => quantity.clone(),
+        }
+    }
+}
+
+/// The ways the serving of a label can fail to parse.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ServingError {
+    #[error("could not parse the household serving: {0}")]
+    Household(ParseError),
+    #[error("could not parse the serving size: {0}")]
+    Size(ParseError),
+    #[error("the label has no serving size")]
+    NoSize,
+}
+
+impl BrandedFoodItem {
+    /// Parse the serving size of the label, the mass or volume FDC scales the nutrients of the
+    /// food with. Unlike [`BrandedFoodItem::serving`], this does not read the household text.
+    pub fn serving_size(&self) -> Result<Quantity, ServingError> {
+        let (size, unit) = match (self.serving_size, self.serving_size_unit.as_deref()) {
+            (Some(size), Some(unit)) => (size, unit),
+            _ => return Err(ServingError::NoSize),
+        };
+        let size = format!("{} {}", size, unit.trim());
+        parse::parse_measure(&size).map_err(ServingError::Size)
+    }
+
+    /// Parse the serving of the label. Labels without household serving text only have the
+    /// canonical serving size.
+    pub fn serving(&self) -> Result<Serving, ServingError> {
+        // parse the canonical serving size
+        let canonical = self.serving_size()?;
+
+        // parse the household serving
+        let text = match self.household_serving_full_text.as_deref() {
+            Some(text) if !text.trim().is_empty() => text,
+            _ => {
+                return Ok(Serving {
+                    household: Vec::new(),
+                    canonical,
+                })
+            }
+        };
+        let household = parse::parse_quantities(text).map_err(ServingError::Household)?;
+        Ok(Serving {
+            household,
+            canonical,
+        })
+    }
+}
+
+/// A label which failed to parse.
+#[derive(Debug, PartialEq)]
+pub struct ServingFailure {
+    pub fdc_id: i32,
+    pub error: ServingError,
+}
+
+/// The outcome of parsing the servings of many branded foods.
+#[derive(Debug, Default, PartialEq)]
+pub struct ServingReport {
+    pub parsed: usize,
+    pub failures: Vec<ServingFailure>,
+}
+
+impl ServingReport {
+    /// parse the serving of every food
+    pub fn new<'a, I>(foods: I) -> ServingReport
+    where
+        I: IntoIterator<Item = &'a BrandedFoodItem>,
+    {
+        let mut report = ServingReport::default();
+        for food in foods {
+            match food.serving() {
+                Ok(_) => report.parsed += 1,
+                Err(error) => report.failures.push(ServingFailure {
+                    fdc_id: food.fdc_id,
+                    error,
+                }),
+            }
+        }
+        report
+    }
+
+    /// the share of the foods whose serving parsed, from 0 to 1
+    pub fn success_rate(&self) -> f32 {
+        let total = self.parsed + self.failures.len();
+        if total == 0 {
+            1.0
+        } else {
+            self.parsed as f32 / total as f32
+        }
+    }
+}
@@ -8,6 +8,72 @@ fn number() {
     assert_eq!(parse::number("1 1/2."), Ok((".", 1.5)));
 }
 
+#[test]
+fn unicode_number() {
+    assert_eq!(parse::number("½ cup"), Ok((" cup", 0.5)));
+    assert_eq!(parse::number("¾"), Ok(("", 0.75)));
+    assert_eq!(parse::number("⅛ tsp"), Ok((" tsp", 0.125)));
+    assert_eq!(parse::number("1½ cups"), Ok((" cups", 1.5)));
+    assert_eq!(parse::number("2 ¼ cups"), Ok((" cups", 2.25)));
+    assert_eq!(parse::number("1⁄2 cup"), Ok(("cup", 0.5)));
+    assert_eq!(parse::number("1 1⁄4 cup"), Ok(("cup", 1.25)));
+    let (rest, third) = parse::number("⅓cup").unwrap();
+    assert_eq!(rest, "cup");
+    assert!((third - 1.0 / 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn unicode_quantity() {
+    use uom::si::{f32::Volume, volume::cup};
+    assert_eq!(
+        parse::quantity("1½ cups"),
+        Ok(("", Quantity::Volume(Volume::new::<cup>(1.5))))
+    );
+    assert_eq!(
+        parse::quantities("¾ cup (180 ml)").map(|(rest, quants)| (rest, quants.len())),
+        Ok(("", 2))
+    );
+    assert_eq!(
+        parse::quantity("½ slice"),
+        Ok(("", Quantity::Nominal(0.5, "slice".into())))
+    );
+}
+
+#[test]
+fn number_words() {
+    assert_eq!(parse::number("one cup"), Ok((" cup", 1.0)));
+    assert_eq!(parse::number("Two slices"), Ok((" slices", 2.0)));
+    assert_eq!(parse::number("twenty-one"), Ok(("", 21.0)));
+    assert_eq!(parse::number("thirty five g"), Ok((" g", 35.0)));
+    assert_eq!(parse::number("a pinch"), Ok((" pinch", 1.0)));
+    assert_eq!(parse::number("an egg"), Ok((" egg", 1.0)));
+    assert_eq!(parse::number("half a cup"), Ok((" cup", 0.5)));
+    assert_eq!(parse::number("a quarter cup"), Ok((" cup", 0.25)));
+    assert_eq!(parse::number("three quarters cup"), Ok((" cup", 0.75)));
+    assert_eq!(parse::number("a dozen eggs"), Ok((" eggs", 12.0)));
+    assert_eq!(parse::number("half a dozen eggs"), Ok((" eggs", 6.0)));
+    assert_eq!(parse::number("one and a half cups"), Ok((" cups", 1.5)));
+    assert!(parse::number("apple").is_err());
+    assert!(parse::number("another").is_err());
+}
+
+#[test]
+fn number_word_quantities() {
+    use uom::si::{f32::Volume, volume::cup};
+    assert_eq!(
+        parse::quantity("half a cup"),
+        Ok(("", Quantity::Volume(Volume::new::<cup>(0.5))))
+    );
+    assert_eq!(
+        parse::quantity("a dozen eggs"),
+        Ok(("", Quantity::Nominal(12.0, "eggs".into())))
+    );
+    assert_eq!(
+        parse::quantity("a handful of nuts"),
+        Ok(("", Quantity::Nominal(1.0, "handful of nuts".into())))
+    );
+}
+
 #[test]
 fn noise_nonexistent() {
     assert_eq!(parse::noise("hello"), Ok(("hello", ())));
@@ -116,3 +182,827 @@ mod quantity {
         );
     }
 }
+
+mod units {
+    use super::*;
+    use crate::testing::assert_close;
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::{gram, microgram, ounce},
+        volume::{cup, deciliter, fluid_ounce, milliliter, tablespoon, teaspoon},
+    };
+
+    fn milliliters(text: &str) -> f32 {
+        match parse::quantity(text) {
+            Ok((_, Quantity::Volume(volume))) => volume.get::<milliliter>(),
+            other => panic!("{:?} should have been a volume, not {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn case_sensitivity() {
+        assert_eq!(
+            parse::quantity("1 T"),
+            Ok(("", Quantity::Volume(Volume::new::<tablespoon>(1.0))))
+        );
+        assert_eq!(
+            parse::quantity("1 t."),
+            Ok(("", Quantity::Volume(Volume::new::<teaspoon>(1.0))))
+        );
+        assert_eq!(
+            parse::quantity("2 TBSP"),
+            Ok(("", Quantity::Volume(Volume::new::<tablespoon>(2.0))))
+        );
+        assert_eq!(
+            parse::quantity("2 C."),
+            Ok(("", Quantity::Volume(Volume::new::<cup>(2.0))))
+        );
+    }
+
+    #[test]
+    fn kitchen_units() {
+        let spoon = Volume::new::<teaspoon>(1.0).get::<milliliter>();
+        assert_close(milliliters("a dash"), spoon / 8.0);
+        assert_close(milliliters("2 pinches"), spoon / 8.0);
+        assert_close(milliliters("1 smidgen"), spoon / 32.0);
+        assert_close(milliliters("5 drops"), 0.25);
+        assert_close(milliliters("1 dessertspoon"), 10.0);
+        assert_eq!(
+            parse::quantity("a pinch of salt"),
+            Ok((
+                " of salt",
+                Quantity::Volume(Volume::new::<teaspoon>(1.0 / 16.0))
+            ))
+        );
+        assert_eq!(
+            parse::quantity("1 stick of butter"),
+            Ok(("", Quantity::Mass(Mass::new::<ounce>(4.0))))
+        );
+        assert_eq!(
+            parse::quantity("1 stick of gum"),
+            Ok(("", Quantity::Nominal(1.0, "stick of gum".into())))
+        );
+    }
+
+    #[test]
+    fn measures() {
+        assert_close(milliliters("1 imperial pint"), 568.261);
+        assert_close(milliliters("1 dry cup"), 275.3105);
+        // the longest unit wins over the shorter one it starts with
+        assert_close(milliliters("1 cup dry"), 275.3105);
+        assert_close(milliliters("2 cups dry"), 2.0 * 275.3105);
+        assert_eq!(
+            parse::quantity("1 cup dried apricots"),
+            Ok((" dried apricots", Quantity::Volume(Volume::new::<cup>(1.0))))
+        );
+        assert_eq!(
+            parse::parse_quantities("1 cup dry (60 g)").unwrap()[0],
+            Quantity::Volume(Volume::new::<uom::si::volume::pint_dry>(0.5))
+        );
+        assert_eq!(
+            parse::quantity("2 dl"),
+            Ok(("", Quantity::Volume(Volume::new::<deciliter>(2.0))))
+        );
+        assert_eq!(
+            parse::quantity("100 mcg"),
+            Ok(("", Quantity::Mass(Mass::new::<microgram>(100.0))))
+        );
+        assert_eq!(
+            parse::quantity("100 µg"),
+            Ok(("", Quantity::Mass(Mass::new::<microgram>(100.0))))
+        );
+        assert_eq!(
+            parse::quantity("8 fl.  oz  "),
+            Ok(("  ", Quantity::Volume(Volume::new::<fluid_ounce>(8.0))))
+        );
+        assert_eq!(
+            parse::parse_measure("28 g"),
+            Ok(Quantity::Mass(Mass::new::<gram>(28.0)))
+        );
+    }
+}
+
+mod conversion {
+    use super::*;
+    use crate::fdc::mock::fixtures;
+    use crate::testing::assert_close;
+    use convert::ConversionError;
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::{gram, ounce},
+        volume::{cup, fluid_ounce, tablespoon},
+    };
+
+    #[test]
+    fn portion_quantities() {
+        let foods = fixtures::foods();
+
+        // sr legacy foods name the unit in the modifier
+        let milk = foods[1].food_portions();
+        assert_eq!(
+            convert::portion_quantity(&milk[1]),
+            Some(Quantity::Volume(Volume::new::<fluid_ounce>(1.0)))
+        );
+
+        // survey foods in the description
+        let egg = foods[2].food_portions();
+        assert_eq!(
+            convert::portion_quantity(&egg[0]),
+            Some(Quantity::Nominal(1.0, "large egg".into()))
+        );
+        assert_eq!(
+            convert::portion_quantity(&egg[1]),
+            Some(Quantity::Volume(Volume::new::<cup>(1.0)))
+        );
+        assert_eq!(convert::portion_quantity(&egg[2]), None);
+
+        // and foundation foods in the measure unit
+        let flour = foods[3].food_portions();
+        assert_eq!(
+            convert::portion_quantity(&flour[1]),
+            Some(Quantity::Volume(Volume::new::<tablespoon>(1.0)))
+        );
+    }
+
+    #[test]
+    fn volume_to_mass() {
+        let foods = fixtures::foods();
+
+        // half a cup of milk weighs half of the cup portion
+        let half_cup = Quantity::Volume(Volume::new::<cup>(0.5));
+        assert_close(half_cup.grams_of(&foods[1]).unwrap(), 122.0);
+
+        // other volumes go through the density of the food
+        let ounces = Quantity::Volume(Volume::new::<fluid_ounce>(2.0));
+        assert_close(ounces.grams_of(&foods[1]).unwrap(), 61.0);
+        let tablespoons = Quantity::Volume(Volume::new::<tablespoon>(4.0));
+        assert_close(tablespoons.grams_of(&foods[3]).unwrap(), 31.25);
+    }
+
+    #[test]
+    fn unconvertible() {
+        let foods = fixtures::foods();
+
+        // masses need no density
+        let mass = Quantity::Mass(Mass::new::<ounce>(1.0));
+        assert_close(mass.grams_of(&foods[0]).unwrap(), 28.3495);
+
+        // but volumes do, which branded foods lack
+        let volume = Quantity::Volume(Volume::new::<cup>(1.0));
+        assert_eq!(volume.grams_of(&foods[0]), Err(ConversionError::NoDensity));
+        assert_eq!(
+            Quantity::Nominal(2.0, "slices".into()).to_mass(None),
+            Err(ConversionError::Nominal("slices".into()))
+        );
+        assert_close(mass.to_mass(None).unwrap().get::<gram>(), 28.3495);
+    }
+}
+
+mod resolution {
+    use super::*;
+    use crate::fdc::{self, mock::fixtures, FoodPortion};
+
+    fn portion(id: i32, modifier: &str, gram_weight: f32) -> FoodPortion {
+        FoodPortion {
+            id,
+            amount: Some(1.0),
+            data_points: None,
+            gram_weight,
+            modifier: Some(modifier.into()),
+            portion_description: None,
+            sequence_number: None,
+            measure_unit: None,
+        }
+    }
+
+    fn bread() -> Vec<FoodPortion> {
+        vec![
+            portion(1, "slice", 25.0),
+            portion(2, "slice, thick", 35.0),
+            portion(3, "loaf", 450.0),
+        ]
+    }
+
+    #[test]
+    fn plurals() {
+        let portions = bread();
+        let slices = Quantity::Nominal(2.0, "slices".into());
+        let resolved = slices.resolve(&portions).unwrap();
+        assert_eq!(resolved.portion.id, 1);
+        assert_eq!(resolved.grams, 50.0);
+        let loaves = Quantity::Nominal(0.5, "Loaves".into());
+        assert_eq!(loaves.resolve(&portions).unwrap().grams, 225.0);
+    }
+
+    #[test]
+    fn ambiguity() {
+        let portions = bread();
+
+        // the exact match wins, but the thick slice makes it less certain
+        let slice = Quantity::Nominal(1.0, "slice".into())
+            .resolve(&portions)
+            .unwrap();
+        assert!(slice.confidence < 1.0 && slice.confidence > 0.5);
+        let thick = Quantity::Nominal(1.0, "thick slice".into())
+            .resolve(&portions)
+            .unwrap();
+        assert_eq!(thick.portion.id, 2);
+        let loaf = Quantity::Nominal(1.0, "loaf".into())
+            .resolve(&portions)
+            .unwrap();
+        assert_eq!(loaf.confidence, 1.0);
+    }
+
+    #[test]
+    fn fuzzy() {
+        let portions = bread();
+        let typo = Quantity::Nominal(2.0, "slics".into())
+            .resolve(&portions)
+            .unwrap();
+        assert_eq!(typo.portion.id, 1);
+        assert!(typo.confidence < 1.0);
+
+        // unrelated units do not resolve
+        assert!(Quantity::Nominal(1.0, "package".into())
+            .resolve(&portions)
+            .is_none());
+        assert!(Quantity::Nominal(1.0, "sl".into())
+            .resolve(&portions)
+            .is_none());
+    }
+
+    #[test]
+    fn empty_portions() {
+        // portions of no amount have no weight per unit
+        let mut empty = portion(4, "slice", 25.0);
+        empty.amount = Some(0.0);
+        assert_eq!(resolve::portion_label(&empty), None);
+        let mut portions = bread();
+        portions.insert(0, empty);
+        let slices = Quantity::Nominal(2.0, "slices".into())
+            .resolve(&portions)
+            .unwrap();
+        assert_eq!(slices.portion.id, 1);
+        assert_eq!(slices.grams, 50.0);
+        let mut negative = portion(5, "loaf", 450.0);
+        negative.amount = Some(-1.0);
+        let loaf = Quantity::Nominal(1.0, "loaf".into());
+        assert!(loaf.resolve(&[negative]).is_none());
+    }
+
+    #[test]
+    fn fixture_portions() {
+        let foods = fixtures::foods_json();
+        let egg = fdc::meta_from_value(foods[2].clone()).unwrap();
+        assert_eq!(
+            resolve::portion_label(&egg.food_portions()[0]),
+            Some((1.0, "large egg".into()))
+        );
+        assert_eq!(resolve::portion_label(&egg.food_portions()[2]), None);
+
+        // the survey food describes its eggs
+        let eggs = Quantity::Nominal(2.0, "large eggs".into());
+        assert_eq!(eggs.grams_of(&egg), Ok(122.0));
+        let resolved = Quantity::Nominal(1.0, "egg".into())
+            .resolve(egg.food_portions())
+            .unwrap();
+        assert_eq!(resolved.grams, 61.0);
+        assert!(resolved.confidence < 1.0);
+        assert_eq!(
+            Quantity::Nominal(1.0, "package".into()).grams_of(&egg),
+            Err(convert::ConversionError::Nominal("package".into()))
+        );
+    }
+}
+
+mod estimates {
+    use super::*;
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::{gram, ounce},
+        volume::{cup, tablespoon},
+    };
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            parse::estimate("2-3 cups of flour"),
+            Ok((
+                " of flour",
+                Estimate {
+                    low: Quantity::Volume(Volume::new::<cup>(2.0)),
+                    high: Some(Quantity::Volume(Volume::new::<cup>(3.0))),
+                    approximate: false,
+                }
+            ))
+        );
+        assert_eq!(
+            parse::estimate("1 to 2 tbsp"),
+            Ok((
+                "",
+                Estimate {
+                    low: Quantity::Volume(Volume::new::<tablespoon>(1.0)),
+                    high: Some(Quantity::Volume(Volume::new::<tablespoon>(2.0))),
+                    approximate: false,
+                }
+            ))
+        );
+        assert_eq!(
+            parse::estimate("1½ \u{2013} 2 slices").map(|(_, est)| est.midpoint()),
+            Ok(Quantity::Nominal(1.75, "slices".into()))
+        );
+        assert_eq!(
+            parse::estimate("2 tomatoes"),
+            Ok(("", Quantity::Nominal(2.0, "tomatoes".into()).into()))
+        );
+    }
+
+    #[test]
+    fn hyphenated_fractions() {
+        // a hyphen before a fraction joins a compound fraction rather than a range
+        assert_eq!(
+            parse::estimate("1-1/2 cups"),
+            Ok(("", Quantity::Volume(Volume::new::<cup>(1.5)).into()))
+        );
+        assert_eq!(
+            parse::estimate("1-1/2 to 2 cups").map(|(_, est)| est.midpoint()),
+            Ok(Quantity::Volume(Volume::new::<cup>(1.75)))
+        );
+    }
+
+    #[test]
+    fn reversed_ranges() {
+        assert!(parse::estimate("3-2 cups").is_err());
+        assert!(parse::estimate("2 to 1 tbsp").is_err());
+        assert!(parse::parse_estimates("3-2 cups").is_err());
+    }
+
+    #[test]
+    fn approximate() {
+        assert_eq!(
+            parse::estimate("about 4 oz"),
+            Ok((
+                "",
+                Estimate {
+                    low: Quantity::Mass(Mass::new::<ounce>(4.0)),
+                    high: None,
+                    approximate: true,
+                }
+            ))
+        );
+        let (_, est) = parse::estimate("approx. 2-4 g").unwrap();
+        assert!(est.approximate && est.is_range());
+        assert_eq!(est.midpoint(), Quantity::Mass(Mass::new::<gram>(3.0)));
+    }
+
+    #[test]
+    fn labels() {
+        let (_, ests) = parse::estimates("2-3 pieces (about 40 g)").unwrap();
+        assert_eq!(ests.len(), 2);
+        assert!(ests[0].is_range() && !ests[0].approximate);
+        assert!(!ests[1].is_range() && ests[1].approximate);
+        assert_eq!(
+            parse::quantities("2-3 pieces (about 40 g)"),
+            Ok((
+                "",
+                vec![
+                    Quantity::Nominal(2.5, "pieces".into()),
+                    Quantity::Mass(Mass::new::<gram>(40.0)),
+                ]
+            ))
+        );
+    }
+}
+
+mod errors {
+    use super::*;
+    use parse::{Expected, ParseError};
+
+    #[test]
+    fn spans() {
+        let error = parse::parse_quantities("1 cup (240 ml").unwrap_err();
+        assert_eq!(error.expected, Expected::ClosingParenthesis);
+        assert_eq!(error.span, 13..13);
+        assert_eq!(error.found(), "the end of the text");
+
+        let error = parse::parse_quantities("2 slices | #3 g").unwrap_err();
+        assert_eq!(error.expected, Expected::Number);
+        assert_eq!(error.span, 11..13);
+
+        let error = parse::parse_quantities("2 ()").unwrap_err();
+        assert_eq!(error.expected, Expected::Unit);
+        assert_eq!(error.span, 2..3);
+
+        // spans never cut a character in half
+        let error = parse::parse_quantity("1\u{a0}cup").unwrap_err();
+        assert_eq!(error.expected, Expected::Unit);
+        assert_eq!(error.span, 1..3);
+        assert_eq!(error.found(), "\"\\u{a0}\"");
+        assert!(error.render().ends_with("\n   ^"));
+        let error = parse::parse_measure("2 µl\u{2003}x").unwrap_err();
+        assert_eq!(error.expected, Expected::End);
+        assert_eq!(error.span, 5..8);
+        assert!(error.render().ends_with("\n      ^"));
+    }
+
+    #[test]
+    fn suggestions() {
+        let error = parse::parse_measure("28 grms").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError {
+                text: "28 grms".into(),
+                span: 3..7,
+                expected: Expected::MassOrVolume,
+                suggestion: Some("grams".into()),
+            }
+        );
+        assert_eq!(
+            parse::parse_measure("2 tablspoons").unwrap_err().suggestion,
+            Some("tablespoons".into())
+        );
+        assert_eq!(parse::parse_measure("1 IU").unwrap_err().suggestion, None);
+        assert_eq!(
+            parse::parse_measure("28 g of chips").unwrap_err().expected,
+            Expected::End
+        );
+        assert!(parse::parse_measure(" 28 g ").is_ok());
+    }
+
+    #[test]
+    fn render() {
+        let error = parse::parse_measure("28 grms").unwrap_err();
+        assert_eq!(
+            error.render(),
+            "expected a unit of mass or volume, found \"grms\"\n  28 grms\n     ^^^^\n  did you mean \"grams\"?"
+        );
+        let error = parse::parse_quantities("½ cup (120 ml").unwrap_err();
+        assert_eq!(
+            error.render(),
+            "expected a closing parenthesis, found the end of the text\n  ½ cup (120 ml\n               ^"
+        );
+    }
+}
+
+#[test]
+fn quantities_in_label_order() {
+    use uom::si::{
+        f32::Volume,
+        volume::{cup, milliliter},
+    };
+    assert_eq!(
+        parse::quantities("1 cup (240 ml)"),
+        Ok((
+            "",
+            vec![
+                Quantity::Volume(Volume::new::<cup>(1.0)),
+                Quantity::Volume(Volume::new::<milliliter>(240.0)),
+            ]
+        ))
+    );
+}
+
+mod servings {
+    use super::*;
+    use crate::fdc::{self, mock::fixtures, BrandedFoodItem, FDCMeta};
+    use serving::{ServingError, ServingReport};
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::gram,
+        volume::{milliliter, tablespoon},
+    };
+
+    fn branded() -> BrandedFoodItem {
+        let foods = fixtures::foods_json();
+        match fdc::meta_from_value(foods[0].clone()).unwrap() {
+            FDCMeta::Branded(meta) => meta,
+            _ => panic!("Should have been a branded food!"),
+        }
+    }
+
+    #[test]
+    fn fixture_serving() {
+        let serving = branded().serving().unwrap();
+        assert_eq!(
+            serving.household,
+            vec![Quantity::Volume(Volume::new::<tablespoon>(1.0))]
+        );
+        assert_eq!(
+            serving.canonical,
+            Quantity::Volume(Volume::new::<milliliter>(14.0))
+        );
+    }
+
+    #[test]
+    fn label_servings() {
+        let mut food = branded();
+        food.household_serving_full_text = Some("2 pieces (28 g)".into());
+        food.serving_size = Some(28.0);
+        food.serving_size_unit = Some("GRM".into());
+        let serving = food.serving().unwrap();
+        assert_eq!(
+            serving.household,
+            vec![
+                Quantity::Nominal(2.0, "pieces".into()),
+                Quantity::Mass(Mass::new::<gram>(28.0)),
+            ]
+        );
+        assert_eq!(serving.canonical, Quantity::Mass(Mass::new::<gram>(28.0)));
+
+        // labels without household text still have a canonical serving
+        food.household_serving_full_text = None;
+        assert!(food.serving().unwrap().household.is_empty());
+    }
+
+    #[test]
+    fn report() {
+        let good = branded();
+        let mut bad_text = branded();
+        bad_text.fdc_id = 1;
+        bad_text.household_serving_full_text = Some("SEE PACKAGE".into());
+        let mut bad_unit = branded();
+        bad_unit.fdc_id = 2;
+        bad_unit.serving_size_unit = Some("IU".into());
+
+        let report = ServingReport::new(vec![&good, &bad_text, &bad_unit]);
+        assert_eq!(report.parsed, 1);
+        assert_eq!(report.failures[0].fdc_id, 1);
+        match &report.failures[0].error {
+            ServingError::Household(error) => {
+                assert_eq!(error.span, 0..3);
+                assert_eq!(error.expected, parse::Expected::Number);
+            }
+            error => panic!("Unexpected error {:?}", error),
+        }
+        match &report.failures[1].error {
+            ServingError::Size(error) => {
+                assert_eq!(&error.text[error.span.clone()], "IU");
+                assert_eq!(error.expected, parse::Expected::MassOrVolume);
+            }
+            error => panic!("Unexpected error {:?}", error),
+        }
+        assert!((report.success_rate() - 1.0 / 3.0).abs() < 1e-6);
+    }
+}
+
+mod compact {
+    use super::*;
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::gram,
+        volume::{cup, milliliter},
+    };
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Ingredient {
+        #[serde(with = "crate::quantities::compact")]
+        quantity: Quantity,
+    }
+
+    #[test]
+    fn json() {
+        let quantities = vec![
+            Quantity::Volume(Volume::new::<cup>(1.5)),
+            Quantity::Mass(Mass::new::<gram>(28.0)),
+            Quantity::Nominal(2.0, "slices".into()),
+        ];
+        let json = serde_json::to_value(&quantities).unwrap();
+        assert_eq!(json[2], serde_json::json!({ "nominal": [2.0, "slices"] }));
+        assert_eq!(
+            serde_json::from_value::<Vec<Quantity>>(json).unwrap(),
+            quantities
+        );
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(Quantity::Mass(Mass::new::<gram>(28.0)).compact(), "28 g");
+        assert_eq!(
+            Quantity::Volume(Volume::new::<cup>(1.5)).compact(),
+            "354.882 ml"
+        );
+        assert_eq!(
+            Quantity::Nominal(2.0, "slices".into()).compact(),
+            "2 slices"
+        );
+        assert_eq!(
+            "1.5 cup".parse::<Quantity>(),
+            Ok(Quantity::Volume(Volume::new::<cup>(1.5)))
+        );
+        assert_eq!(
+            "28 g"
+                .parse::<Quantity>()
+                .map(|quantity| quantity.compact()),
+            Ok("28 g".into())
+        );
+        assert!("1.5 cup of rice".parse::<Quantity>().is_err());
+    }
+
+    #[test]
+    fn fields() {
+        let ingredient =
+            serde_json::from_str::<Ingredient>(r#"{ "quantity": "1.5 cup" }"#).unwrap();
+        let json = serde_json::to_string(&ingredient).unwrap();
+        assert_eq!(json, r#"{"quantity":"354.882 ml"}"#);
+        match serde_json::from_str::<Ingredient>(&json).unwrap().quantity {
+            Quantity::Volume(volume) => {
+                assert!((volume.get::<milliliter>() - 354.882).abs() < 1e-3)
+            }
+            quantity => panic!("{:?} should have been a volume", quantity),
+        }
+        let error = serde_json::from_str::<Ingredient>(r#"{ "quantity": "cup" }"#).unwrap_err();
+        assert!(error.to_string().starts_with("expected a number"));
+    }
+}
+
+mod formatting {
+    use super::*;
+    use format::{Formatter, UnitSystem};
+    use uom::si::{
+        f32::{Mass, Volume},
+        mass::{gram, milligram, ounce},
+        volume::{cup, milliliter, tablespoon, teaspoon},
+    };
+
+    fn volume<U>(amount: f32) -> Quantity
+    where
+        U: uom::si::volume::Unit + uom::si::volume::Conversion<f32, T = f32>,
+    {
+        Quantity::Volume(Volume::new::<U>(amount))
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(volume::<cup>(1.0).to_string(), "236.59 ml");
+        assert_eq!(format!("{:.0}", volume::<cup>(1.0)), "237 ml");
+        assert_eq!(volume::<milliliter>(1500.0).to_string(), "1.5 l");
+        assert_eq!(Quantity::Mass(Mass::new::<gram>(28.0)).to_string(), "28 g");
+        assert_eq!(
+            Quantity::Mass(Mass::new::<milligram>(250.0)).to_string(),
+            "250 mg"
+        );
+        assert_eq!(
+            Quantity::Nominal(2.0, "slices".into()).to_string(),
+            "2 slices"
+        );
+    }
+
+    #[test]
+    fn us_customary() {
+        let us = Formatter::new(UnitSystem::UsCustomary);
+        assert_eq!(us.format(&volume::<teaspoon>(3.0)), "1 tbsp");
+        assert_eq!(us.format(&volume::<teaspoon>(2.0)), "2 tsp");
+        assert_eq!(us.format(&volume::<tablespoon>(2.0)), "1 fl oz");
+        assert_eq!(us.format(&volume::<cup>(1.5)), "1.5 cups");
+        assert_eq!(us.format(&volume::<cup>(6.0)), "1.5 qt");
+        assert_eq!(
+            us.format(&Quantity::Mass(Mass::new::<ounce>(24.0))),
+            "1.5 lb"
+        );
+        assert_eq!(
+            us.with_precision(1)
+                .format(&Quantity::Mass(Mass::new::<gram>(28.0))),
+            "1 oz"
+        );
+    }
+
+    #[test]
+    fn kitchen() {
+        let kitchen = Formatter::new(UnitSystem::Kitchen);
+        assert_eq!(kitchen.format(&volume::<cup>(1.5)), "1 ½ cups");
+        assert_eq!(kitchen.format(&volume::<cup>(0.5)), "½ cup");
+        assert_eq!(kitchen.format(&volume::<tablespoon>(4.0)), "¼ cup");
+        assert_eq!(kitchen.format(&volume::<tablespoon>(2.0)), "2 tbsp");
+        assert_eq!(kitchen.format(&volume::<teaspoon>(1.5)), "1 ½ tsp");
+        assert_eq!(kitchen.format(&volume::<cup>(2.0 / 3.0)), "⅔ cup");
+        assert_eq!(kitchen.format(&volume::<cup>(0.6)), "⅝ cup");
+        assert_eq!(
+            kitchen.with_denominator(4).format(&volume::<cup>(0.6)),
+            "⅔ cup"
+        );
+        assert_eq!(kitchen.format(&volume::<teaspoon>(0.01)), "0.01 tsp");
+        assert_eq!(
+            kitchen.format(&Quantity::Nominal(2.25, "slices".into())),
+            "2 ¼ slices"
+        );
+    }
+
+    #[test]
+    fn parsed() {
+        let kitchen = Formatter::new(UnitSystem::Kitchen);
+        let (_, quantity) = parse::quantity("1½ cups").unwrap();
+        assert_eq!(kitchen.format(&quantity), "1 ½ cups");
+        let (_, quantity) = parse::quantity(&kitchen.format(&quantity)).unwrap();
+        assert_eq!(kitchen.format(&quantity), "1 ½ cups");
+    }
+}
+
+mod sums {
+    use super::*;
+    use crate::quantities::arithmetic::ArithmeticError;
+    use uom::si::{
+        f32::{Mass, MassDensity, Volume},
+        mass::gram,
+        mass_density::gram_per_cubic_centimeter,
+        volume::{cup, milliliter, tablespoon},
+    };
+
+    fn milliliters(quantity: &Quantity) -> f32 {
+        match quantity {
+            Quantity::Volume(volume) => volume.get::<milliliter>(),
+            quantity => panic!("{:?} should have been a volume", quantity),
+        }
+    }
+
+    #[test]
+    fn same_kind() {
+        let sum = Quantity::Volume(Volume::new::<cup>(1.0))
+            .checked_add(&Quantity::Volume(Volume::new::<tablespoon>(4.0)))
+            .unwrap();
+        assert!(
+            (milliliters(&sum) - milliliters(&Quantity::Volume(Volume::new::<cup>(1.25)))).abs()
+                < 1e-3
+        );
+        assert_eq!(
+            Quantity::Mass(Mass::new::<gram>(28.0))
+                .checked_add(&Quantity::Mass(Mass::new::<gram>(14.0))),
+            Ok(Quantity::Mass(Mass::new::<gram>(42.0)))
+        );
+        assert_eq!(
+            Quantity::Nominal(2.0, "slices".into())
+                .checked_add(&Quantity::Nominal(1.0, "slices".into())),
+            Ok(Quantity::Nominal(3.0, "slices".into()))
+        );
+        assert_eq!(
+            Quantity::Nominal(1.5, "slices".into()) * 2.0,
+            Quantity::Nominal(3.0, "slices".into())
+        );
+        assert_eq!(
+            Quantity::Mass(Mass::new::<gram>(14.0)) * 2.0,
+            Quantity::Mass(Mass::new::<gram>(28.0))
+        );
+    }
+
+    #[test]
+    fn mixed_kinds() {
+        let butter = Quantity::Mass(Mass::new::<gram>(100.0));
+        let milk = Quantity::Volume(Volume::new::<milliliter>(100.0));
+        assert_eq!(
+            butter.checked_add(&milk),
+            Err(ArithmeticError::Dimensions("mass", "volume"))
+        );
+        assert_eq!(
+            Quantity::Nominal(1.0, "slice".into())
+                .checked_add(&Quantity::Nominal(1.0, "egg".into())),
+            Err(ArithmeticError::Units("slice".into(), "egg".into()))
+        );
+        let density = MassDensity::new::<gram_per_cubic_centimeter>(0.5);
+        assert_eq!(
+            butter.add_with(&milk, Some(density)),
+            Ok(Quantity::Mass(Mass::new::<gram>(150.0)))
+        );
+        let sum = milk.add_with(&butter, Some(density)).unwrap();
+        assert!((milliliters(&sum) - 300.0).abs() < 1e-3);
+        assert!(Quantity::Nominal(1.0, "egg".into())
+            .add_with(&butter, Some(density))
+            .is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        let large = Quantity::Volume(Volume::new::<cup>(1.0));
+        let spoon = Quantity::Volume(Volume::new::<tablespoon>(1.0));
+        assert!(spoon < large);
+        assert!(Quantity::Nominal(1.0, "slice".into()) < Quantity::Nominal(2.0, "slice".into()));
+        assert_eq!(
+            Quantity::Nominal(1.0, "slice".into())
+                .partial_cmp(&Quantity::Nominal(2.0, "egg".into())),
+            None
+        );
+        assert_eq!(
+            large.partial_cmp(&Quantity::Mass(Mass::new::<gram>(1.0))),
+            None
+        );
+    }
+
+    #[test]
+    fn totals() {
+        let log = ["1 cup", "2 slices", "4 tbsp", "30 g", "1 slices", "1 egg"]
+            .iter()
+            .map(|text| parse::parse_quantity(text).unwrap())
+            .collect::<Vec<_>>();
+        let totals = Quantity::totals(&log, None);
+        assert_eq!(totals.len(), 4);
+        assert!(
+            (milliliters(&totals[0]) - milliliters(&Quantity::Volume(Volume::new::<cup>(1.25))))
+                .abs()
+                < 1e-3
+        );
+        assert_eq!(totals[1], Quantity::Nominal(3.0, "slices".into()));
+        assert_eq!(totals[2], Quantity::Mass(Mass::new::<gram>(30.0)));
+        assert_eq!(totals[3], Quantity::Nominal(1.0, "egg".into()));
+
+        let density = MassDensity::new::<gram_per_cubic_centimeter>(1.0);
+        assert_eq!(Quantity::totals(&log, Some(density)).len(), 3);
+    }
+}
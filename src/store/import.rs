@@ -0,0 +1,969 @@
+//! Importers for the bulk datasets FDC publishes at
+//! [fdc.nal.usda.gov](https://fdc.nal.usda.gov/download-datasets.html), so that the store can be
+//! filled without an api key.
+//!
+//! The JSON datasets hold a single object like `{"BrandedFoods": [...]}` whose foods are already
+//! in the full format of the api, so they are streamed one food at a time and checked against
+//! [`FDCMeta`](crate::fdc::FDCMeta) before being stored. The CSV datasets split each food over
+//! several tables, so the importer stages a skeleton of every food in `food.csv` in a table of its
+//! own and the rows of the other tables in another, keyed by the food they belong to. Once every
+//! table is read, each food is assembled once and those which deserialize are promoted to the
+//! store, so that neither a food which does not assemble nor an import which fails partway
+//! replaces a food we have already stored.
+//!
+//! Datasets are read on a blocking thread which hands the foods over to the store in batches, so
+//! that even the multi-gigabyte branded dataset is never held in memory.
+
+use super::{fdc_id, put_json, FoodStore, Result, StoreError};
+use crate::fdc;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{json, Map, Value};
+use sqlx::{Any, Executor, Row as _};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use zip::ZipArchive;
+
+/// The number of operations written to the database in a single transaction.
+const BATCH_SIZE: usize = 500;
+
+/// The outcome of an import. Foods are skipped when FDC does not serve their type through the
+/// api, like the sub-sample foods of the foundation dataset, or when they do not deserialize.
+/// The rows of the other tables of a CSV dataset are skipped when they belong to no food we
+/// import, or refer to an entry a lookup table like `nutrient.csv` does not have.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+    pub skipped_rows: usize,
+}
+
+/// The operations the reading thread hands over to the store.
+enum Op {
+    /// store a complete food
+    Put(Value),
+    /// stage a food to be assembled
+    Stage(Value),
+    /// set fields of a staged food
+    Set(i32, Map<String, Value>),
+    /// append items to an array field of a staged food
+    Append(i32, &'static str, Vec<Value>),
+}
+
+impl FoodStore {
+    /// Import a dataset, deciding on the importer by the path: directories are read as CSV
+    /// datasets, `.json` files as JSON datasets, and zip files as whichever they contain.
+    pub async fn import<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        let path = path.as_ref().to_path_buf();
+        if path.is_dir() {
+            return self.import_csv_dir(path).await;
+        }
+        let is_zip = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("zip"))
+            .unwrap_or(false);
+        if !is_zip {
+            return self.import_json(File::open(path)?).await;
+        }
+        let archive = ZipArchive::new(File::open(&path)?)?;
+        if find_entry(&archive, ".json").is_some() {
+            self.import_json_zip(path).await
+        } else {
+            self.import_csv_zip(path).await
+        }
+    }
+
+    /// Import a JSON dataset from a reader.
+    pub async fn import_json<R: Read + Send + 'static>(&self, reader: R) -> Result<ImportReport> {
+        let (imported, skipped) = self.run(move |tx| read_json(reader, tx)).await?;
+        Ok(ImportReport {
+            imported: imported.len(),
+            skipped,
+            skipped_rows: 0,
+        })
+    }
+
+    /// Import the JSON dataset inside of a zip file, as FDC distributes them.
+    pub async fn import_json_zip<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        let path = path.as_ref().to_path_buf();
+        let (imported, skipped) = self
+            .run(move |tx| {
+                let mut archive = ZipArchive::new(File::open(path)?)?;
+                let name = find_entry(&archive, ".json").ok_or_else(|| missing("a .json file"))?;
+                let entry = archive.by_name(&name)?;
+                read_json(entry, tx)
+            })
+            .await?;
+        Ok(ImportReport {
+            imported: imported.len(),
+            skipped,
+            skipped_rows: 0,
+        })
+    }
+
+    /// Import a CSV dataset which has been unzipped to a directory.
+    pub async fn import_csv_dir<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        let path = path.as_ref().to_path_buf();
+        self.stage().await?;
+        let res = self.run(move |tx| read_csv(&mut CsvDir(path), tx)).await;
+        self.promote(res).await
+    }
+
+    /// Import the CSV dataset inside of a zip file, as FDC distributes them.
+    pub async fn import_csv_zip<P: AsRef<Path>>(&self, path: P) -> Result<ImportReport> {
+        let path = path.as_ref().to_path_buf();
+        self.stage().await?;
+        let res = self
+            .run(move |tx| {
+                let archive = ZipArchive::new(File::open(path)?)?;
+                read_csv(&mut CsvZip(archive), tx)
+            })
+            .await;
+        self.promote(res).await
+    }
+
+    /// Run a reader on a blocking thread while writing its operations to the database,
+    /// returning the ids of the stored foods and what the reader returned.
+    async fn run<F, T>(&self, read: F) -> Result<(Vec<i32>, T)>
+    where
+        F: FnOnce(&Sender<Op>) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::channel(4 * BATCH_SIZE);
+        let reader = tokio::task::spawn_blocking(move || read(&tx));
+        let written = self.write(&mut rx).await;
+        drop(rx);
+        let read = reader.await;
+        // a failed write cancels the reader, so its error is the interesting one
+        let imported = written?;
+        Ok((imported, read??))
+    }
+
+    /// Write the operations in batches, returning the ids of the stored foods.
+    async fn write(&self, rx: &mut Receiver<Op>) -> Result<Vec<i32>> {
+        let now = Utc::now();
+        let mut imported = Vec::new();
+        let mut seq = 0;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        while let Some(op) = rx.recv().await {
+            batch.push(op);
+            while batch.len() < BATCH_SIZE {
+                match rx.try_recv() {
+                    Ok(op) => batch.push(op),
+                    Err(_) => break,
+                }
+            }
+            let mut transaction = self.pool.begin().await?;
+            for op in batch.drain(..) {
+                match op {
+                    Op::Put(food) => {
+                        put_json(&mut *transaction, &food, now).await?;
+                        imported.push(fdc_id(&food)?);
+                    }
+                    Op::Stage(food) => {
+                        put_staged(&mut *transaction, &food).await?;
+                        imported.push(fdc_id(&food)?);
+                    }
+                    Op::Set(fdc_id, fields) => {
+                        let part = Part::new(fdc_id, seq, None, Value::Object(fields));
+                        put_part(&mut *transaction, part).await?;
+                        seq += 1;
+                    }
+                    Op::Append(fdc_id, key, items) => {
+                        let part = Part::new(fdc_id, seq, Some(key), Value::Array(items));
+                        put_part(&mut *transaction, part).await?;
+                        seq += 1;
+                    }
+                }
+            }
+            transaction.commit().await?;
+        }
+        Ok(imported)
+    }
+
+    /// Create the tables CSV imports assemble their foods from, clearing anything a previous
+    /// import left behind. The skeletons of the foods and the rows of the other tables are staged
+    /// apart, so that every row is written once and every food is assembled once.
+    async fn stage(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS fdc_foods_staged (
+                fdc_id INTEGER PRIMARY KEY,
+                payload TEXT NOT NULL
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS fdc_food_parts_staged (
+                fdc_id INTEGER NOT NULL,
+                seq BIGINT NOT NULL,
+                field TEXT,
+                payload TEXT NOT NULL,
+                PRIMARY KEY (fdc_id, seq)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        self.clear_staged().await
+    }
+
+    /// helper function which empties the tables of staged foods
+    async fn clear_staged(&self) -> Result<()> {
+        sqlx::query("DELETE FROM fdc_foods_staged")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM fdc_food_parts_staged")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Store the assembled foods of a CSV import which deserialize, skipping the rest. The staged
+    /// foods are cleared whether or not the import succeeded.
+    async fn promote(&self, res: Result<(Vec<i32>, ImportReport)>) -> Result<ImportReport> {
+        let res = match res {
+            Ok((staged, report)) => self.promote_staged(staged, report).await,
+            Err(e) => Err(e),
+        };
+        self.clear_staged().await?;
+        res
+    }
+
+    /// helper function which assembles the staged foods and moves those which deserialize into
+    /// the store
+    async fn promote_staged(
+        &self,
+        staged: Vec<i32>,
+        mut report: ImportReport,
+    ) -> Result<ImportReport> {
+        let now = Utc::now();
+        for chunk in staged.chunks(BATCH_SIZE) {
+            let mut transaction = self.pool.begin().await?;
+            for &fdc_id in chunk {
+                let food = assemble(&mut transaction, fdc_id)
+                    .await?
+                    .filter(|food| fdc::meta_from_value(food.clone()).is_ok());
+                match food {
+                    Some(food) => {
+                        put_json(&mut *transaction, &food, now).await?;
+                        report.imported += 1;
+                    }
+                    None => report.skipped += 1,
+                }
+            }
+            transaction.commit().await?;
+        }
+        Ok(report)
+    }
+}
+
+/// helper function which stages the json of a food through any connection
+async fn put_staged<'e, E>(executor: E, food: &Value) -> Result<()>
+where
+    E: Executor<'e, Database = Any>,
+{
+    sqlx::query(
+        "INSERT INTO fdc_foods_staged (fdc_id, payload) VALUES ($1, $2)
+        ON CONFLICT (fdc_id) DO UPDATE SET payload = excluded.payload",
+    )
+    .bind(fdc_id(food)?)
+    .bind(food.to_string())
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// A row of another table staged for a food: either fields to set, or the items to append to an
+/// array field, which are applied in the order they were read.
+struct Part {
+    fdc_id: i32,
+    seq: i64,
+    field: Option<&'static str>,
+    payload: Value,
+}
+
+impl Part {
+    fn new(fdc_id: i32, seq: i64, field: Option<&'static str>, payload: Value) -> Part {
+        Part {
+            fdc_id,
+            seq,
+            field,
+            payload,
+        }
+    }
+}
+
+/// helper function which stages a part of a food through any connection
+async fn put_part<'e, E>(executor: E, part: Part) -> Result<()>
+where
+    E: Executor<'e, Database = Any>,
+{
+    sqlx::query(
+        "INSERT INTO fdc_food_parts_staged (fdc_id, seq, field, payload) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(part.fdc_id)
+    .bind(part.seq)
+    .bind(part.field)
+    .bind(part.payload.to_string())
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// helper function which assembles a staged food from its skeleton and its parts
+async fn assemble(connection: &mut sqlx::AnyConnection, fdc_id: i32) -> Result<Option<Value>> {
+    let row = sqlx::query("SELECT payload FROM fdc_foods_staged WHERE fdc_id = $1")
+        .bind(fdc_id)
+        .fetch_optional(&mut *connection)
+        .await?;
+    let mut food: Value = match row {
+        Some(row) => serde_json::from_str(&row.try_get::<String, _>("payload")?)?,
+        None => return Ok(None),
+    };
+    let parts = sqlx::query(
+        "SELECT field, payload FROM fdc_food_parts_staged WHERE fdc_id = $1 ORDER BY seq",
+    )
+    .bind(fdc_id)
+    .fetch_all(&mut *connection)
+    .await?;
+    for part in parts {
+        let field = part.try_get::<Option<String>, _>("field")?;
+        let payload = serde_json::from_str(&part.try_get::<String, _>("payload")?)?;
+        match (field, payload) {
+            (None, Value::Object(fields)) => fields
+                .into_iter()
+                .for_each(|(key, value)| food[key] = value),
+            (Some(key), Value::Array(items)) => match food[&key].as_array_mut() {
+                Some(array) => array.extend(items),
+                None => food[key] = Value::Array(items),
+            },
+            _ => {}
+        }
+    }
+    Ok(Some(food))
+}
+
+/// The `dataType` of the foods the api serves, by the `data_type` FDC writes in `food.csv`.
+fn data_type(csv_data_type: &str) -> Option<&'static str> {
+    match csv_data_type {
+        "branded_food" => Some("Branded"),
+        "foundation_food" => Some("Foundation"),
+        "sr_legacy_food" => Some("SR Legacy"),
+        "survey_fndds_food" => Some("Survey (FNDDS)"),
+        "experimental_food" => Some("Experimental"),
+        _ => None,
+    }
+}
+
+/// helper function which creates the error of a dataset missing a file
+fn missing(name: &str) -> StoreError {
+    StoreError::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("the dataset has no {}", name),
+    ))
+}
+
+/// helper function which creates the error of a reader whose store has stopped listening
+fn cancelled() -> StoreError {
+    StoreError::Io(io::Error::other("the import was cancelled"))
+}
+
+/// helper function which hands an operation over to the store
+fn send(tx: &Sender<Op>, op: Op) -> Result<()> {
+    tx.blocking_send(op).map_err(|_| cancelled())
+}
+
+/// The name of the first entry of the archive which ends with the suffix.
+fn find_entry<R: Read + io::Seek>(archive: &ZipArchive<R>, suffix: &str) -> Option<String> {
+    archive
+        .file_names()
+        .find(|name| name.ends_with(suffix))
+        .map(String::from)
+}
+
+/// Stream the foods of a JSON dataset, returning the number of foods skipped.
+fn read_json<R: Read>(reader: R, tx: &Sender<Op>) -> Result<usize> {
+    let mut skipped = 0;
+    let mut stopped = false;
+    let mut on_food = |food: Value| {
+        if fdc::meta_from_value(food.clone()).is_err() {
+            skipped += 1;
+            true
+        } else {
+            stopped = tx.blocking_send(Op::Put(food)).is_err();
+            !stopped
+        }
+    };
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let res = deserializer.deserialize_any(FoodsVisitor(&mut on_food));
+    match res {
+        _ if stopped => Err(cancelled()),
+        Err(e) => Err(e.into()),
+        Ok(()) => Ok(skipped),
+    }
+}
+
+/// Visits the foods of a JSON dataset, which are either an array of foods or an object whose
+/// values are arrays of foods. The callback returns whether to continue.
+struct FoodsVisitor<'a>(&'a mut dyn FnMut(Value) -> bool);
+
+impl<'de, 'a> Visitor<'de> for FoodsVisitor<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of foods, or an object of arrays of foods")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while let Some(food) = seq.next_element::<Value>()? {
+            if !(self.0)(food) {
+                return Err(de::Error::custom("the import was cancelled"));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        let on_food = self.0;
+        while map.next_key::<String>()?.is_some() {
+            map.next_value_seed(FoodsSeed(&mut *on_food))?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializes an array of foods with a [`FoodsVisitor`].
+struct FoodsSeed<'a>(&'a mut dyn FnMut(Value) -> bool);
+
+impl<'de, 'a> DeserializeSeed<'de> for FoodsSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_seq(FoodsVisitor(self.0))
+    }
+}
+
+/// The tables of a CSV dataset.
+trait CsvFiles {
+    /// open a table like `"food.csv"`, if the dataset has it
+    fn open(&mut self, name: &str) -> Result<Option<Box<dyn Read + '_>>>;
+}
+
+/// A CSV dataset unzipped to a directory.
+struct CsvDir(PathBuf);
+
+impl CsvFiles for CsvDir {
+    fn open(&mut self, name: &str) -> Result<Option<Box<dyn Read + '_>>> {
+        let path = self.0.join(name);
+        if path.is_file() {
+            Ok(Some(Box::new(File::open(path)?)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A zipped CSV dataset, whose tables FDC nests in a directory named after the release.
+struct CsvZip(ZipArchive<File>);
+
+impl CsvFiles for CsvZip {
+    fn open(&mut self, name: &str) -> Result<Option<Box<dyn Read + '_>>> {
+        let nested = format!("/{}", name);
+        let entry = self
+            .0
+            .file_names()
+            .find(|entry| *entry == name || entry.ends_with(&nested))
+            .map(String::from);
+        match entry {
+            Some(entry) => Ok(Some(Box::new(self.0.by_name(&entry)?))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A row of a CSV table, read by column name.
+struct Row<'a> {
+    columns: &'a HashMap<String, usize>,
+    record: &'a csv::StringRecord,
+}
+
+impl<'a> Row<'a> {
+    /// the value of a column, with empty values being [`None`]
+    fn str(&self, column: &str) -> Option<&'a str> {
+        let i = *self.columns.get(column)?;
+        self.record.get(i).filter(|value| !value.is_empty())
+    }
+
+    fn int(&self, column: &str) -> Option<i64> {
+        self.str(column)?.parse().ok()
+    }
+
+    fn float(&self, column: &str) -> Option<f64> {
+        self.str(column)?.parse().ok()
+    }
+
+    /// the value of a column as a json string, or null
+    fn json_str(&self, column: &str) -> Value {
+        self.str(column).map(Value::from).unwrap_or(Value::Null)
+    }
+
+    /// the value of a column as a json number, or null
+    fn json_float(&self, column: &str) -> Value {
+        self.float(column).map(Value::from).unwrap_or(Value::Null)
+    }
+
+    /// the value of a column as a json integer, or null
+    fn json_int(&self, column: &str) -> Value {
+        self.int(column).map(Value::from).unwrap_or(Value::Null)
+    }
+}
+
+/// Read every row of a table, returning whether the dataset has it.
+fn rows<F>(files: &mut dyn CsvFiles, name: &str, mut f: F) -> Result<bool>
+where
+    F: FnMut(Row) -> Result<()>,
+{
+    let reader = match files.open(name)? {
+        Some(reader) => reader,
+        None => return Ok(false),
+    };
+    let mut reader = csv::Reader::from_reader(reader);
+    let columns = reader
+        .headers()?
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.trim().to_string(), i))
+        .collect::<HashMap<_, _>>();
+    let mut record = csv::StringRecord::new();
+    while reader.read_record(&mut record)? {
+        f(Row {
+            columns: &columns,
+            record: &record,
+        })?;
+    }
+    Ok(true)
+}
+
+/// Read a lookup table into a map by its `id` column.
+fn lookup<F>(files: &mut dyn CsvFiles, name: &str, f: F) -> Result<HashMap<i64, Value>>
+where
+    F: Fn(&Row) -> Value,
+{
+    lookup_by(files, name, "id", f)
+}
+
+/// Read a lookup table into a map by one of its integer columns.
+fn lookup_by<F>(
+    files: &mut dyn CsvFiles,
+    name: &str,
+    column: &str,
+    f: F,
+) -> Result<HashMap<i64, Value>>
+where
+    F: Fn(&Row) -> Value,
+{
+    let mut map = HashMap::new();
+    rows(files, name, |row| {
+        if let Some(id) = row.int(column) {
+            map.insert(id, f(&row));
+        }
+        Ok(())
+    })?;
+    Ok(map)
+}
+
+/// Collects consecutive rows of the same food into a single [`Op::Append`].
+struct Appender<'a> {
+    tx: &'a Sender<Op>,
+    key: &'static str,
+    current: Option<(i32, Vec<Value>)>,
+}
+
+impl<'a> Appender<'a> {
+    fn new(tx: &'a Sender<Op>, key: &'static str) -> Appender<'a> {
+        Appender {
+            tx,
+            key,
+            current: None,
+        }
+    }
+
+    fn push(&mut self, fdc_id: i32, item: Value) -> Result<()> {
+        match &mut self.current {
+            Some((id, items)) if *id == fdc_id => items.push(item),
+            _ => {
+                self.flush()?;
+                self.current = Some((fdc_id, vec![item]));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self.current.take() {
+            Some((fdc_id, items)) => send(self.tx, Op::Append(fdc_id, self.key, items)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// helper function which sets the fields of a staged food which have a value
+fn set(tx: &Sender<Op>, fdc_id: i32, fields: &[(&str, Value)]) -> Result<()> {
+    let fields = fields
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect();
+    send(tx, Op::Set(fdc_id, fields))
+}
+
+/// Stream the tables of a CSV dataset, returning the number of foods and rows skipped.
+///
+/// Every table FDC publishes for the foods the api serves is read, apart from the tables of the
+/// nutrient conversion factors and of the components of foundation foods, so foods of those
+/// datasets are imported without them.
+fn read_csv(files: &mut dyn CsvFiles, tx: &Sender<Op>) -> Result<ImportReport> {
+    // the lookup tables are small enough to keep in memory
+    let nutrients = lookup(files, "nutrient.csv", |row| {
+        json!({
+            "id": row.json_int("id"),
+            "number": row.json_str("nutrient_nbr"),
+            "name": row.json_str("name"),
+            // FDC writes ranks like "600.0"
+            "rank": row.float("rank").map(|rank| rank as i64),
+            "unitName": row.json_str("unit_name"),
+        })
+    })?;
+    let measure_units = lookup(
+        files,
+        "measure_unit.csv",
+        |row| json!({ "id": row.json_int("id"), "name": row.json_str("name") }),
+    )?;
+    let attribute_types = lookup(files, "food_attribute_type.csv", |row| {
+        json!({
+            "id": row.json_int("id"),
+            "name": row.json_str("name"),
+            "description": row.json_str("description"),
+        })
+    })?;
+    let sources = lookup(files, "food_nutrient_source.csv", |row| {
+        json!({
+            "id": row.json_int("id"),
+            "code": row.json_str("code"),
+            "description": row.json_str("description"),
+        })
+    })?;
+    let derivations = lookup(files, "food_nutrient_derivation.csv", |row| {
+        let mut derivation = json!({
+            "id": row.json_int("id"),
+            "code": row.json_str("code"),
+            "description": row.json_str("description"),
+        });
+        if let Some(source) = row.int("source_id").and_then(|id| sources.get(&id)) {
+            derivation["foodNutrientSource"] = source.clone();
+        }
+        derivation
+    })?;
+    let categories = lookup(files, "food_category.csv", |row| {
+        json!({
+            "id": row.json_int("id"),
+            "code": row.json_str("code"),
+            "description": row.json_str("description"),
+        })
+    })?;
+    let wweia_categories = lookup_by(
+        files,
+        "wweia_food_category.csv",
+        "wweia_food_category",
+        |row| {
+            json!({
+                "wweiaFoodCategoryCode": row.json_int("wweia_food_category"),
+                "wweiaFoodCategoryDescription": row.json_str("wweia_food_category_description"),
+            })
+        },
+    )?;
+
+    // stage a skeleton of every food the api serves, keeping the data types of the foods to
+    // patch, and the samples which foundation foods were derived from
+    let mut data_types = HashMap::new();
+    let mut samples = HashMap::new();
+    let mut skipped = 0;
+    let mut skipped_rows = 0;
+    let has_foods = rows(files, "food.csv", |row| {
+        let fdc_id = row.int("fdc_id");
+        let data_type = row.str("data_type").and_then(data_type);
+        let (fdc_id, data_type) = match (fdc_id, data_type) {
+            (Some(fdc_id), Some(data_type)) => (fdc_id as i32, data_type),
+            (Some(fdc_id), None) => {
+                let sample = json!({
+                    "fdcId": fdc_id,
+                    "description": row.json_str("description"),
+                    "publicationDate": row.json_str("publication_date"),
+                });
+                samples.insert(fdc_id, sample);
+                skipped += 1;
+                return Ok(());
+            }
+            _ => {
+                skipped += 1;
+                return Ok(());
+            }
+        };
+        let mut food = json!({
+            "fdcId": fdc_id,
+            "dataType": data_type,
+            "description": row.json_str("description"),
+            "publicationDate": row.json_str("publication_date"),
+            "foodNutrients": [],
+            "foodAttributes": [],
+        });
+        if data_type != "Branded" {
+            food["foodPortions"] = json!([]);
+        }
+        if data_type == "Foundation" || data_type == "SR Legacy" {
+            let category = row
+                .int("food_category_id")
+                .and_then(|id| categories.get(&id));
+            if let Some(category) = category {
+                food["foodCategory"] = category.clone();
+            }
+        }
+        data_types.insert(fdc_id, data_type);
+        send(tx, Op::Stage(food))
+    })?;
+    if !has_foods {
+        return Err(missing("food.csv"));
+    }
+    let staged = |row: &Row| {
+        let fdc_id = row.int("fdc_id")? as i32;
+        data_types
+            .get(&fdc_id)
+            .map(|data_type| (fdc_id, *data_type))
+    };
+
+    // patch in the metadata of each data type
+    rows(files, "branded_food.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, "Branded")) => fdc_id,
+            _ => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let fields = [
+            ("brandOwner", row.json_str("brand_owner")),
+            ("brandName", row.json_str("brand_name")),
+            ("subbrandName", row.json_str("subbrand_name")),
+            ("gtinUpc", row.json_str("gtin_upc")),
+            ("ingredients", row.json_str("ingredients")),
+            ("servingSize", row.json_float("serving_size")),
+            ("servingSizeUnit", row.json_str("serving_size_unit")),
+            (
+                "householdServingFullText",
+                row.json_str("household_serving_fulltext"),
+            ),
+            ("brandedFoodCategory", row.json_str("branded_food_category")),
+            ("dataSource", row.json_str("data_source")),
+            ("marketCountry", row.json_str("market_country")),
+            ("modifiedDate", row.json_str("modified_date")),
+            ("availableDate", row.json_str("available_date")),
+        ];
+        set(tx, fdc_id, &fields)
+    })?;
+    rows(files, "foundation_food.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, "Foundation")) => fdc_id,
+            _ => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let fields = [
+            ("ndbNumber", row.json_int("NDB_number")),
+            ("footNote", row.json_str("footnote")),
+        ];
+        set(tx, fdc_id, &fields)
+    })?;
+    rows(files, "sr_legacy_food.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, "SR Legacy")) => fdc_id,
+            _ => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        set(tx, fdc_id, &[("ndbNumber", row.json_int("NDB_number"))])
+    })?;
+    rows(files, "survey_fndds_food.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, "Survey (FNDDS)")) => fdc_id,
+            _ => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        // FDC renamed the category column in later releases
+        let category = row
+            .int("wweia_category_code")
+            .or_else(|| row.int("wweia_category_number"))
+            .and_then(|code| wweia_categories.get(&code))
+            .cloned()
+            .unwrap_or(Value::Null);
+        let fields = [
+            ("foodCode", row.json_str("food_code")),
+            ("startDate", row.json_str("start_date")),
+            ("endDate", row.json_str("end_date")),
+            ("wweiaFoodCategory", category),
+        ];
+        set(tx, fdc_id, &fields)
+    })?;
+
+    // append the ingredients of survey foods and the samples of foundation foods
+    let mut input_foods = Appender::new(tx, "inputFoods");
+    rows(files, "input_food.csv", |row| {
+        let input_food = match staged(&row) {
+            Some((fdc_id, "Survey (FNDDS)")) => (
+                fdc_id,
+                json!({
+                    "id": row.json_int("id"),
+                    "amount": row.json_float("amount"),
+                    "foodDescription": row.json_str("sr_description"),
+                    "ingredientCode": row.json_int("sr_code"),
+                    "ingredientDescription": row.json_str("sr_description"),
+                    "ingredientWeight": row.json_float("gram_weight"),
+                    "portionCode": row.json_str("portion_code"),
+                    "portionDescription": row.json_str("portion_description"),
+                    "sequenceNumber": row.json_int("seq_num"),
+                    "surveyFlag": row.json_int("survey_flag"),
+                    "unit": row.json_str("unit"),
+                }),
+            ),
+            Some((fdc_id, "Foundation")) => {
+                let sample = row
+                    .int("fdc_id_of_input_food")
+                    .and_then(|id| samples.get(&id));
+                let mut input_food = json!({ "id": row.json_int("id") });
+                if let Some(sample) = sample {
+                    input_food["foodDescription"] = sample["description"].clone();
+                    input_food["inputFood"] = sample.clone();
+                }
+                (fdc_id, input_food)
+            }
+            _ => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        input_foods.push(input_food.0, input_food.1)
+    })?;
+    input_foods.flush()?;
+
+    // append the portions
+    let mut portions = Appender::new(tx, "foodPortions");
+    rows(files, "food_portion.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, _)) => fdc_id,
+            None => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let mut portion = json!({
+            "id": row.json_int("id"),
+            "amount": row.json_float("amount"),
+            "dataPoints": row.json_int("data_points"),
+            "gramWeight": row.json_float("gram_weight"),
+            "modifier": row.json_str("modifier"),
+            "portionDescription": row.json_str("portion_description"),
+            "sequenceNumber": row.json_int("seq_num"),
+        });
+        if let Some(unit) = row
+            .int("measure_unit_id")
+            .and_then(|id| measure_units.get(&id))
+        {
+            portion["measureUnit"] = unit.clone();
+        }
+        portions.push(fdc_id, portion)
+    })?;
+    portions.flush()?;
+
+    // append the attributes
+    let mut attributes = Appender::new(tx, "foodAttributes");
+    rows(files, "food_attribute.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, _)) => fdc_id,
+            None => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let attribute_type = row
+            .int("food_attribute_type_id")
+            .and_then(|id| attribute_types.get(&id));
+        let attribute_type = match attribute_type {
+            Some(attribute_type) => attribute_type.clone(),
+            None => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let attribute = json!({
+            "id": row.json_int("id"),
+            "sequenceNumber": row.json_int("seq_num"),
+            "name": row.json_str("name"),
+            "value": row.str("value").unwrap_or_default(),
+            "foodAttributeType": attribute_type,
+        });
+        attributes.push(fdc_id, attribute)
+    })?;
+    attributes.flush()?;
+
+    // append the nutrients
+    let mut food_nutrients = Appender::new(tx, "foodNutrients");
+    rows(files, "food_nutrient.csv", |row| {
+        let fdc_id = match staged(&row) {
+            Some((fdc_id, _)) => fdc_id,
+            None => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let nutrient = match row.int("nutrient_id").and_then(|id| nutrients.get(&id)) {
+            Some(nutrient) => nutrient.clone(),
+            None => {
+                skipped_rows += 1;
+                return Ok(());
+            }
+        };
+        let mut food_nutrient = json!({
+            "type": "FoodNutrient",
+            "id": row.json_int("id"),
+            "nutrient": nutrient,
+            "amount": row.json_float("amount"),
+            "dataPoints": row.json_int("data_points"),
+            "min": row.json_float("min"),
+            "max": row.json_float("max"),
+            "median": row.json_float("median"),
+        });
+        if let Some(derivation) = row.int("derivation_id").and_then(|id| derivations.get(&id)) {
+            food_nutrient["foodNutrientDerivation"] = derivation.clone();
+        }
+        food_nutrients.push(fdc_id, food_nutrient)
+    })?;
+    food_nutrients.flush()?;
+
+    Ok(ImportReport {
+        imported: 0,
+        skipped,
+        skipped_rows,
+    })
+}
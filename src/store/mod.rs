@@ -0,0 +1,282 @@
+//! This module provides the [`FoodStore`] struct which keeps the FDC foods we have seen in the
+//! configured database. Foods are stored as the json FDC responded with, alongside the time they
+//! were fetched and the date FDC published them, so that [`FoodStore::foods`] can serve them
+//! without a request to FDC until they go stale. The store can also be filled offline from the
+//! bulk datasets FDC publishes, through the importers of the [`import`] module.
+
+pub mod import;
+
+use crate::env::Environment;
+use crate::fdc::{self, FDCMeta, FDCService, FdcError};
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use reqwest::Client;
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{Any, AnyPool, Executor, Row};
+
+/// The ways the store can fail.
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Fdc(#[from] FdcError),
+    #[error("payload is not valid json: {0}")]
+    Payload(#[from] serde_json::Error),
+    #[error("could not read the dataset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not unzip the dataset: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("could not read the dataset csv: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("the dataset reader failed: {0}")]
+    Reader(#[from] tokio::task::JoinError),
+    #[error("the food has no integer fdcId")]
+    NoFdcId,
+}
+
+type Result<T> = std::result::Result<T, StoreError>;
+
+/// A food we have stored, along with when we fetched it.
+#[derive(Debug)]
+pub struct StoredFood {
+    pub fdc_id: i32,
+    pub fetched_at: DateTime<Utc>,
+    pub publication_date: Option<NaiveDate>,
+    pub food: FDCMeta,
+}
+
+/// The database table of FDC foods.
+#[derive(Clone, Debug)]
+pub struct FoodStore {
+    pool: AnyPool,
+    pub max_age: Duration,
+}
+
+impl FoodStore {
+    /// The age after which a stored food is refreshed from FDC.
+    pub fn default_max_age() -> Duration {
+        Duration::days(30)
+    }
+
+    /// generate a store on top of a pool, creating the table if it does not exist yet
+    pub async fn new(pool: AnyPool) -> Result<FoodStore> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS fdc_foods (
+                fdc_id INTEGER PRIMARY KEY,
+                data_type TEXT NOT NULL,
+                description TEXT NOT NULL,
+                publication_date TEXT,
+                fetched_at BIGINT NOT NULL,
+                payload TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        Ok(FoodStore {
+            pool,
+            max_age: FoodStore::default_max_age(),
+        })
+    }
+
+    /// connect to a database url like `"postgres://localhost/nutrition"` or `"sqlite:foods.db"`
+    pub async fn connect(url: &str) -> Result<FoodStore> {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new().connect(url).await?;
+        FoodStore::new(pool).await
+    }
+
+    /// Connect to the database of the environment. Sqlite urls name the database themselves,
+    /// while any other url is joined with `DATABASE_NAME`.
+    pub async fn from_env(environment: &Environment) -> Result<FoodStore> {
+        FoodStore::connect(&database_url(environment)).await
+    }
+
+    /// change the age after which a stored food is refreshed from FDC
+    pub fn with_max_age(self, max_age: Duration) -> FoodStore {
+        FoodStore { max_age, ..self }
+    }
+
+    /// Store the json of a food in the full format, replacing any previous version of it.
+    pub async fn put(&self, food: &serde_json::Value, fetched_at: DateTime<Utc>) -> Result<()> {
+        put_json(&self.pool, food, fetched_at).await
+    }
+
+    /// Get a stored food, no matter how stale.
+    pub async fn get(&self, fdc_id: i32) -> Result<Option<StoredFood>> {
+        let row = sqlx::query(
+            "SELECT fdc_id, publication_date, fetched_at, payload FROM fdc_foods
+            WHERE fdc_id = $1",
+        )
+        .bind(fdc_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(stored_food).transpose()
+    }
+
+    /// Remove a stored food.
+    pub async fn remove(&self, fdc_id: i32) -> Result<()> {
+        sqlx::query("DELETE FROM fdc_foods WHERE fdc_id = $1")
+            .bind(fdc_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether a stored food is older than the maximum age.
+    pub fn is_stale(&self, food: &StoredFood, now: DateTime<Utc>) -> bool {
+        now - food.fetched_at > self.max_age
+    }
+
+    /// Get foods through the store. Foods which are missing or stale are requested from FDC and
+    /// stored, in batches which fail on their own. Stale foods whose request fails are served
+    /// instead so that foods we have already seen remain available offline, while missing foods
+    /// whose request fails are an error. Stored foods which no longer deserialize are requested
+    /// again as if they were missing. Foods FDC does not know of are left out of the result.
+    pub async fn foods(
+        &self,
+        service: &FDCService,
+        client: &Client,
+        fdc_ids: &[i32],
+    ) -> Result<Vec<StoredFood>> {
+        // split the stored foods from the ones we need to fetch
+        let now = Utc::now();
+        let mut stored = HashMap::with_capacity(fdc_ids.len());
+        let mut to_fetch = Vec::new();
+        for &fdc_id in fdc_ids {
+            match readable(self.get(fdc_id).await)? {
+                Some(food) if !self.is_stale(&food, now) => {
+                    stored.insert(fdc_id, food);
+                }
+                Some(food) => {
+                    stored.insert(fdc_id, food);
+                    to_fetch.push(fdc_id);
+                }
+                None => to_fetch.push(fdc_id),
+            }
+        }
+
+        // fetch and store the rest, falling back to stale foods if we are offline
+        if !to_fetch.is_empty() {
+            for (fdc_id, food) in service.v1_foods_json(client, &to_fetch).await {
+                // only store what deserializes, so that a stale food is not replaced by a food
+                // we cannot read
+                match food.and_then(|food| Ok((fdc::meta_from_value(food.clone())?, food))) {
+                    Ok((_, food)) => {
+                        self.put(&food, now).await?;
+                        if let Some(food) = self.get(fdc_id).await? {
+                            stored.insert(fdc_id, food);
+                        }
+                    }
+                    // foods FDC does not know of are left out
+                    Err(FdcError::Missing { .. }) => {}
+                    Err(e) if !stored.contains_key(&fdc_id) => return Err(e.into()),
+                    Err(_) => {}
+                }
+            }
+        }
+
+        Ok(fdc_ids
+            .iter()
+            .filter_map(|fdc_id| stored.remove(fdc_id))
+            .collect())
+    }
+}
+
+/// helper function which treats a stored food that no longer deserializes as missing
+fn readable(res: Result<Option<StoredFood>>) -> Result<Option<StoredFood>> {
+    match res {
+        Err(StoreError::Payload(_)) | Err(StoreError::Fdc(FdcError::Deserialize { .. })) => {
+            Ok(None)
+        }
+        res => res,
+    }
+}
+
+/// helper function which stores the json of a food through any connection
+async fn put_json<'e, E>(
+    executor: E,
+    food: &serde_json::Value,
+    fetched_at: DateTime<Utc>,
+) -> Result<()>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let fdc_id = fdc_id(food)?;
+    let data_type = food["dataType"].as_str().unwrap_or_default();
+    let description = food["description"].as_str().unwrap_or_default();
+    let publication_date = food["publicationDate"]
+        .as_str()
+        .and_then(parse_date)
+        .map(|date| date.to_string());
+    sqlx::query(
+        "INSERT INTO fdc_foods
+            (fdc_id, data_type, description, publication_date, fetched_at, payload)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (fdc_id) DO UPDATE SET
+            data_type = excluded.data_type,
+            description = excluded.description,
+            publication_date = excluded.publication_date,
+            fetched_at = excluded.fetched_at,
+            payload = excluded.payload",
+    )
+    .bind(fdc_id)
+    .bind(data_type)
+    .bind(description)
+    .bind(publication_date)
+    .bind(fetched_at.timestamp())
+    .bind(food.to_string())
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The url of the environment's database.
+pub fn database_url(environment: &Environment) -> String {
+    if environment.database_url.starts_with("sqlite:") {
+        environment.database_url.clone()
+    } else {
+        format!(
+            "{}/{}",
+            environment.database_url.trim_end_matches('/'),
+            environment.database_name
+        )
+    }
+}
+
+/// The `fdcId` of the json of a food.
+fn fdc_id(food: &serde_json::Value) -> Result<i32> {
+    food["fdcId"]
+        .as_i64()
+        .and_then(|fdc_id| i32::try_from(fdc_id).ok())
+        .ok_or(StoreError::NoFdcId)
+}
+
+/// FDC writes dates like `"4/1/2019"` in the full format and `"2019-04-01"` in search results.
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%m/%d/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .ok()
+}
+
+/// helper function which turns a row of the table into a stored food
+fn stored_food(row: AnyRow) -> Result<StoredFood> {
+    let payload = row.try_get::<String, _>("payload")?;
+    let publication_date = row.try_get::<Option<String>, _>("publication_date")?;
+    Ok(StoredFood {
+        fdc_id: row.try_get::<i32, _>("fdc_id")?,
+        // a fetch time out of range makes the food stale rather than unreadable
+        fetched_at: Utc
+            .timestamp_opt(row.try_get::<i64, _>("fetched_at")?, 0)
+            .single()
+            .unwrap_or(DateTime::<Utc>::MIN_UTC),
+        publication_date: publication_date.as_deref().and_then(parse_date),
+        food: fdc::meta_from_value(serde_json::from_str(&payload)?)?,
+    })
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,579 @@
+use super::*;
+use crate::fdc::mock::{fixtures, MockResponse, MockServer};
+
+use sqlx::any::AnyPoolOptions;
+
+/// A store in a fresh in-memory sqlite database. The pool holds on to its single connection,
+/// as each connection to `sqlite::memory:` is a database of its own.
+async fn get_store() -> FoodStore {
+    sqlx::any::install_default_drivers();
+    let pool = AnyPoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    FoodStore::new(pool).await.unwrap()
+}
+
+async fn get_mock_service() -> (FDCService, MockServer) {
+    let server = MockServer::start().await;
+    let service = FDCService::new("MOCK_KEY")
+        .with_base_url(server.url())
+        .with_retry_policy(crate::fdc::RetryPolicy::none());
+    (service, server)
+}
+
+#[tokio::test]
+async fn put_get() {
+    let store = get_store().await;
+    assert!(store.get(173323).await.unwrap().is_none());
+
+    // store the sr legacy food
+    let fetched_at = Utc.timestamp_opt(1_600_000_000, 0).unwrap();
+    let foods = fixtures::foods_json();
+    store.put(&foods[1], fetched_at).await.unwrap();
+    let stored = store.get(173323).await.unwrap().unwrap();
+    assert_eq!(stored.fdc_id, 173323);
+    assert_eq!(stored.fetched_at, fetched_at);
+    assert_eq!(stored.publication_date, NaiveDate::from_ymd_opt(2019, 4, 1));
+    match stored.food {
+        FDCMeta::SRLegacy(meta) => assert_eq!(meta.food_portions[0].id, 92296),
+        _ => panic!("Should have been an sr legacy food!"),
+    };
+
+    // storing it again replaces it
+    store.put(&foods[1], Utc::now()).await.unwrap();
+    let stored = store.get(173323).await.unwrap().unwrap();
+    assert!(stored.fetched_at > fetched_at);
+}
+
+#[tokio::test]
+async fn read_through() {
+    let store = get_store().await;
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(200, fixtures::FOODS)],
+    );
+
+    // the first request goes to FDC
+    let ids = [1455408, 173323, 1103005, 329370];
+    let foods = store.foods(&service, &client, &ids).await.unwrap();
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        ids.to_vec()
+    );
+    assert_eq!(server.requests().len(), 1);
+
+    // the second is served from the store, in the order asked for
+    let foods = store
+        .foods(&service, &client, &[329370, 1455408])
+        .await
+        .unwrap();
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        vec![329370, 1455408]
+    );
+    assert_eq!(server.requests().len(), 1);
+
+    // foods we have not seen are fetched on their own
+    let foods = store
+        .foods(&service, &client, &[329370, 1455409])
+        .await
+        .unwrap();
+    assert_eq!(foods.len(), 1);
+    assert_eq!(
+        server.requests()[1].json()["fdcIds"],
+        serde_json::json!([1455409])
+    );
+}
+
+#[tokio::test]
+async fn stale() {
+    let store = get_store().await.with_max_age(Duration::zero());
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    let long_ago = Utc.timestamp_opt(1_500_000_000, 0).unwrap();
+    store
+        .put(&fixtures::foods_json()[0], long_ago)
+        .await
+        .unwrap();
+
+    // stale foods are refreshed
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![
+            MockResponse::json(200, format!("[{}]", fixtures::foods_json()[0])),
+            MockResponse::json(503, ""),
+        ],
+    );
+    let foods = store.foods(&service, &client, &[1455408]).await.unwrap();
+    assert!(foods[0].fetched_at > long_ago);
+    assert_eq!(server.requests().len(), 1);
+
+    // while offline, stale foods are still served
+    let foods = store.foods(&service, &client, &[1455408]).await.unwrap();
+    assert_eq!(foods[0].fdc_id, 1455408);
+    assert_eq!(server.requests().len(), 2);
+
+    // but missing foods are an error
+    match store.foods(&service, &client, &[1455408, 173323]).await {
+        Err(StoreError::Fdc(FdcError::Batch { source, .. })) => {
+            assert_eq!(source.status().map(|status| status.as_u16()), Some(503))
+        }
+        res => panic!("Should have been a server error: {:?}", res),
+    }
+}
+
+#[tokio::test]
+async fn unreadable_refresh() {
+    let store = get_store().await.with_max_age(Duration::zero());
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    let long_ago = Utc.timestamp_opt(1_500_000_000, 0).unwrap();
+    store
+        .put(&fixtures::foods_json()[0], long_ago)
+        .await
+        .unwrap();
+
+    // a refreshed food which does not deserialize leaves the stale one in place
+    let food = serde_json::json!({"fdcId": 1455408, "dataType": "Branded"});
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(200, format!("[{}]", food))],
+    );
+    let foods = store.foods(&service, &client, &[1455408]).await.unwrap();
+    assert_eq!(foods[0].fetched_at, long_ago);
+    assert_eq!(
+        store.get(1455408).await.unwrap().unwrap().fetched_at,
+        long_ago
+    );
+}
+
+#[tokio::test]
+async fn corrupt_rows() {
+    let store = get_store().await;
+
+    // foods without an id are not stored as food 0
+    let food = serde_json::json!({"dataType": "Branded", "description": ""});
+    assert!(matches!(
+        store.put(&food, Utc::now()).await,
+        Err(StoreError::NoFdcId)
+    ));
+    assert!(store.get(0).await.unwrap().is_none());
+
+    // a fetch time out of range makes the food stale
+    store
+        .put(&fixtures::foods_json()[1], Utc::now())
+        .await
+        .unwrap();
+    sqlx::query("UPDATE fdc_foods SET fetched_at = $1")
+        .bind(i64::MAX)
+        .execute(&store.pool)
+        .await
+        .unwrap();
+    let stored = store.get(173323).await.unwrap().unwrap();
+    assert!(store.is_stale(&stored, Utc::now()));
+}
+
+#[tokio::test]
+async fn failed_batch() {
+    let store = get_store().await.with_max_age(Duration::zero());
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    let long_ago = Utc.timestamp_opt(1_500_000_000, 0).unwrap();
+    store
+        .put(&fixtures::foods_json()[0], long_ago)
+        .await
+        .unwrap();
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![
+            MockResponse::json(200, format!("[{}]", fixtures::foods_json()[1])),
+            MockResponse::json(503, ""),
+        ],
+    );
+
+    // the foods of a batch which succeeded are kept when a later batch fails
+    let mut fdc_ids = vec![173323];
+    fdc_ids.extend(1..20);
+    fdc_ids.push(1455408);
+    let foods = store.foods(&service, &client, &fdc_ids).await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(
+        foods.iter().map(|food| food.fdc_id).collect::<Vec<_>>(),
+        vec![173323, 1455408]
+    );
+    assert_eq!(foods[1].fetched_at, long_ago);
+    assert!(store.get(173323).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn unreadable() {
+    let store = get_store().await;
+    let (service, server) = get_mock_service().await;
+    let client = reqwest::Client::new();
+    let food = serde_json::json!({"fdcId": 1455408, "dataType": "Branded"});
+    store.put(&food, Utc::now()).await.unwrap();
+    store
+        .put(&fixtures::foods_json()[1], Utc::now())
+        .await
+        .unwrap();
+    assert!(store.get(1455408).await.is_err());
+
+    // stored foods which no longer deserialize are fetched again
+    server.mount(
+        "POST",
+        "/v1/foods",
+        vec![MockResponse::json(
+            200,
+            format!("[{}]", fixtures::foods_json()[0]),
+        )],
+    );
+    let foods = store
+        .foods(&service, &client, &[1455408, 173323])
+        .await
+        .unwrap();
+    assert_eq!(foods.len(), 2);
+    assert_eq!(server.requests().len(), 1);
+    assert!(matches!(foods[0].food, FDCMeta::Branded(_)));
+    assert!(store.get(1455408).await.is_ok());
+}
+
+#[test]
+fn env_database_url() {
+    let environment = Environment {
+        database_url: "postgres://localhost:5432/".into(),
+        database_name: "nutrition".into(),
+        fdc_key: "KEY".into(),
+        fdc_url: fdc::FDC_URL.into(),
+    };
+    assert_eq!(
+        database_url(&environment),
+        "postgres://localhost:5432/nutrition"
+    );
+    let environment = Environment {
+        database_url: "sqlite:foods.db".into(),
+        ..environment
+    };
+    assert_eq!(database_url(&environment), "sqlite:foods.db");
+}
+
+/// A fresh directory for the datasets of a test.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("nutrition-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// helper function which zips files under a release directory, as FDC does
+fn write_zip(path: &std::path::Path, files: &[(&str, String)]) {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, contents) in files {
+        zip.start_file(
+            format!("FoodData_Central_2021-10-28/{}", name),
+            zip::write::FileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// A JSON dataset of the fixture foods, along with a food the api does not serve.
+fn json_dataset() -> String {
+    let mut foods = fixtures::foods_json();
+    foods.push(serde_json::json!({"fdcId": 1, "dataType": "Sub-sample food"}));
+    serde_json::json!({ "SRLegacyFoods": foods }).to_string()
+}
+
+/// A CSV dataset of an sr legacy food, a complete branded food, a branded food without its
+/// ingredients or serving size, a foundation food along with the sub-sample food it was derived
+/// from, and a survey food. The nutrients of the foods are interleaved, and one belongs to the
+/// sub-sample.
+fn csv_dataset() -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "food.csv",
+            "\"fdc_id\",\"data_type\",\"description\",\"food_category_id\",\"publication_date\"
+\"173323\",\"sr_legacy_food\",\"Milk, reduced fat, fluid, 2% milkfat\",\"1\",\"2019-04-01\"
+\"1455408\",\"branded_food\",\"WESSON Vegetable Oil 1 GAL\",\"\",\"2021-03-19\"
+\"1455409\",\"branded_food\",\"WESSON Canola Oil 1 GAL\",\"\",\"2021-03-19\"
+\"1104647\",\"sub_sample_food\",\"Milk, sample 1\",\"1\",\"2020-10-30\"
+\"746782\",\"foundation_food\",\"Milk, whole, 3.25% milkfat, with added vitamin D\",\"1\",\"2019-04-01\"
+\"1097512\",\"survey_fndds_food\",\"Milk, whole\",\"\",\"2020-10-30\"
+"
+            .into(),
+        ),
+        (
+            "food_category.csv",
+            "\"id\",\"code\",\"description\"\n\"1\",\"0100\",\"Dairy and Egg Products\"\n".into(),
+        ),
+        (
+            "sr_legacy_food.csv",
+            "\"fdc_id\",\"NDB_number\"\n\"173323\",\"1174\"\n".into(),
+        ),
+        (
+            "foundation_food.csv",
+            "\"fdc_id\",\"NDB_number\",\"footnote\"\n\"746782\",\"1211\",\"\"\n".into(),
+        ),
+        (
+            "survey_fndds_food.csv",
+            "\"fdc_id\",\"food_code\",\"wweia_category_code\",\"start_date\",\"end_date\"
+\"1097512\",\"11111000\",\"1002\",\"2017-01-01\",\"2018-12-31\"
+"
+            .into(),
+        ),
+        (
+            "wweia_food_category.csv",
+            "\"wweia_food_category\",\"wweia_food_category_description\"\n\"1002\",\"Milk, whole\"\n".into(),
+        ),
+        (
+            "input_food.csv",
+            "\"id\",\"fdc_id\",\"fdc_id_of_input_food\",\"seq_num\",\"amount\",\"sr_code\",\"sr_description\",\"unit\",\"portion_code\",\"portion_description\",\"gram_weight\",\"retention_code\"
+\"9671\",\"746782\",\"1104647\",\"\",\"\",\"\",\"\",\"\",\"\",\"\",\"\",\"\"
+\"11319\",\"1097512\",\"\",\"1\",\"100.0\",\"1211\",\"Milk, whole, 3.25% milkfat, with added vitamin D\",\"GM\",\"0\",\"Quantity not specified\",\"100.0\",\"0\"
+"
+            .into(),
+        ),
+        (
+            "branded_food.csv",
+            "\"fdc_id\",\"brand_owner\",\"gtin_upc\",\"ingredients\",\"serving_size\",\"serving_size_unit\",\"household_serving_fulltext\",\"branded_food_category\",\"data_source\",\"modified_date\",\"available_date\"
+\"1455408\",\"Richardson Oilseed Products (US) Limited\",\"027000612323\",\"Vegetable Oil\",\"14.0\",\"ml\",\"1 Tbsp\",\"Oils Edible\",\"GDSN\",\"2017-07-14\",\"2021-03-19\"
+\"1455409\",\"Richardson Oilseed Products (US) Limited\",\"027000612324\",\"\",\"\",\"\",\"\",\"Oils Edible\",\"GDSN\",\"2017-07-14\",\"2021-03-19\"
+"
+            .into(),
+        ),
+        (
+            "measure_unit.csv",
+            "\"id\",\"name\"\n\"1000\",\"cup\"\n\"1013\",\"fl oz\"\n".into(),
+        ),
+        (
+            "food_portion.csv",
+            "\"id\",\"fdc_id\",\"seq_num\",\"amount\",\"measure_unit_id\",\"portion_description\",\"modifier\",\"gram_weight\",\"data_points\",\"footnote\",\"min_year_acquired\"
+\"92296\",\"173323\",\"1\",\"1.0\",\"1000\",\"\",\"\",\"244.0\",\"\",\"\",\"\"
+\"92297\",\"173323\",\"2\",\"1.0\",\"1013\",\"\",\"\",\"30.5\",\"\",\"\",\"\"
+"
+            .into(),
+        ),
+        (
+            "nutrient.csv",
+            "\"id\",\"name\",\"unit_name\",\"nutrient_nbr\",\"rank\"
+\"1003\",\"Protein\",\"G\",\"203\",\"600.0\"
+\"1004\",\"Total lipid (fat)\",\"G\",\"204\",\"800.0\"
+"
+            .into(),
+        ),
+        (
+            "food_nutrient_source.csv",
+            "\"id\",\"code\",\"description\"\n\"1\",\"1\",\"Analytical or derived from analytical\"\n".into(),
+        ),
+        (
+            "food_nutrient_derivation.csv",
+            "\"id\",\"code\",\"description\",\"source_id\"\n\"1\",\"A\",\"Analytical\",\"1\"\n".into(),
+        ),
+        (
+            "food_nutrient.csv",
+            "\"id\",\"fdc_id\",\"nutrient_id\",\"amount\",\"data_points\",\"derivation_id\",\"min\",\"max\",\"median\",\"footnote\",\"min_year_acquired\"
+\"1283674\",\"173323\",\"1003\",\"3.3\",\"24\",\"1\",\"\",\"\",\"\",\"\",\"\"
+\"13706976\",\"1455408\",\"1004\",\"100.0\",\"\",\"\",\"\",\"\",\"\",\"\",\"\"
+\"1283675\",\"173323\",\"1004\",\"1.98\",\"24\",\"1\",\"\",\"\",\"\",\"\",\"\"
+\"13706978\",\"1104647\",\"1004\",\"3.5\",\"1\",\"1\",\"\",\"\",\"\",\"\",\"\"
+"
+            .into(),
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn import_json() {
+    let store = get_store().await;
+    let report = store
+        .import_json(std::io::Cursor::new(json_dataset()))
+        .await
+        .unwrap();
+    assert_eq!(
+        report,
+        import::ImportReport {
+            imported: 4,
+            skipped: 1,
+            skipped_rows: 0
+        }
+    );
+    let stored = store.get(1103005).await.unwrap().unwrap();
+    assert_eq!(
+        stored.publication_date,
+        NaiveDate::from_ymd_opt(2020, 10, 30)
+    );
+    assert!(store.get(1).await.unwrap().is_none());
+
+    // the zipped dataset is found through its extension
+    let dir = temp_dir("import-json");
+    let path = dir.join("FoodData_Central_sr_legacy_food_json_2021-10-28.zip");
+    write_zip(
+        &path,
+        &[(
+            "FoodData_Central_sr_legacy_food_json_2021-10-28.json",
+            json_dataset(),
+        )],
+    );
+    let store = get_store().await;
+    assert_eq!(store.import(&path).await.unwrap().imported, 4);
+    assert!(store.get(1455408).await.unwrap().is_some());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn import_csv() {
+    let dir = temp_dir("import-csv");
+    for (name, contents) in csv_dataset() {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+    let store = get_store().await;
+    let report = store.import(&dir).await.unwrap();
+    assert_eq!(
+        report,
+        import::ImportReport {
+            imported: 5,
+            skipped: 1,
+            skipped_rows: 1
+        }
+    );
+
+    // the tables are assembled into foods of the full format, in the order of their rows
+    let stored = store.get(173323).await.unwrap().unwrap();
+    assert_eq!(stored.publication_date, NaiveDate::from_ymd_opt(2019, 4, 1));
+    match stored.food {
+        FDCMeta::SRLegacy(meta) => {
+            assert_eq!(meta.ndb_number, Some(1174));
+            assert_eq!(meta.food_category.unwrap().code.as_deref(), Some("0100"));
+            assert_eq!(meta.food_portions.len(), 2);
+            assert_eq!(meta.food_portions[1].gram_weight, 30.5);
+            assert_eq!(meta.food_nutrients.len(), 2);
+            let fat = &meta.food_nutrients[1];
+            assert_eq!(
+                fat.nutrient.as_ref().unwrap().number.as_deref(),
+                Some("204")
+            );
+            assert_eq!(fat.nutrient.as_ref().unwrap().rank, Some(800));
+            let source = fat
+                .food_nutrient_derivation
+                .as_ref()
+                .and_then(|derivation| derivation.food_nutrient_source.as_ref())
+                .unwrap();
+            assert_eq!(source.code, "1");
+        }
+        _ => panic!("Should have been an sr legacy food!"),
+    };
+    match store.get(1455408).await.unwrap().unwrap().food {
+        FDCMeta::Branded(meta) => {
+            assert_eq!(meta.serving_size, Some(14.0));
+            assert_eq!(meta.household_serving_full_text.as_deref(), Some("1 Tbsp"));
+        }
+        _ => panic!("Should have been a branded food!"),
+    };
+
+    // branded foods without their ingredients or serving size are kept
+    match store.get(1455409).await.unwrap().unwrap().food {
+        FDCMeta::Branded(meta) => {
+            assert!(meta.ingredients.is_none());
+            assert!(meta.serving_size.is_none());
+            assert_eq!(meta.gtin_upc.as_deref(), Some("027000612324"));
+        }
+        _ => panic!("Should have been a branded food!"),
+    };
+
+    // the metadata of the other data types is patched in
+    match store.get(746782).await.unwrap().unwrap().food {
+        FDCMeta::Foundation(meta) => {
+            assert_eq!(meta.ndb_number, Some(1211));
+            assert_eq!(
+                meta.food_category.unwrap().description.as_deref(),
+                Some("Dairy and Egg Products")
+            );
+            let sample = meta.input_foods[0].input_food.as_ref().unwrap();
+            assert_eq!(sample.fdc_id, 1104647);
+            assert_eq!(sample.description, "Milk, sample 1");
+        }
+        _ => panic!("Should have been a foundation food!"),
+    };
+    match store.get(1097512).await.unwrap().unwrap().food {
+        FDCMeta::Survey(meta) => {
+            assert_eq!(meta.food_code.as_deref(), Some("11111000"));
+            assert_eq!(
+                meta.wweia_food_category.unwrap().wweia_food_category_code,
+                1002
+            );
+            assert_eq!(meta.input_foods[0].ingredient_code, Some(1211));
+            assert_eq!(meta.input_foods[0].ingredient_weight, Some(100.0));
+        }
+        _ => panic!("Should have been a survey food!"),
+    };
+
+    // the zipped dataset holds the same tables
+    let path = dir.join("FoodData_Central_csv_2021-10-28.zip");
+    write_zip(&path, &csv_dataset());
+    let store = get_store().await;
+    assert_eq!(store.import(&path).await.unwrap(), report);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn import_csv_over_stored() {
+    let dir = temp_dir("import-csv-over-stored");
+    // a food without a description does not deserialize
+    let dataset = csv_dataset()
+        .into_iter()
+        .map(|(name, contents)| (name, contents.replace("WESSON Vegetable Oil 1 GAL", "")))
+        .collect::<Vec<_>>();
+    for (name, contents) in &dataset {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+    let store = get_store().await;
+    let fetched_at = Utc.timestamp_opt(1_600_000_000, 0).unwrap();
+    let branded = fixtures::foods_json().remove(0);
+    assert_eq!(branded["fdcId"], 1455408);
+    store.put(&branded, fetched_at).await.unwrap();
+
+    // a food which does not assemble leaves the stored one alone
+    let report = store.import(&dir).await.unwrap();
+    assert_eq!(
+        report,
+        import::ImportReport {
+            imported: 4,
+            skipped: 2,
+            skipped_rows: 1
+        }
+    );
+    let stored = store.get(1455408).await.unwrap().unwrap();
+    assert_eq!(stored.fetched_at, fetched_at);
+    assert!(matches!(stored.food, FDCMeta::Branded(_)));
+
+    // an import which fails partway stores nothing
+    let store = get_store().await;
+    store.put(&branded, fetched_at).await.unwrap();
+    let mut food_nutrient = dataset
+        .iter()
+        .find(|(name, _)| *name == "food_nutrient.csv")
+        .unwrap()
+        .1
+        .clone();
+    food_nutrient.push_str("\"13706977\",\"1455408\"\n");
+    std::fs::write(dir.join("food_nutrient.csv"), food_nutrient).unwrap();
+    assert!(store.import(&dir).await.is_err());
+    assert!(store.get(173323).await.unwrap().is_none());
+    assert_eq!(
+        store.get(1455408).await.unwrap().unwrap().fetched_at,
+        fetched_at
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,13 @@
+//! Helpers shared by the tests of every module.
+
+/// Assert that two floats agree to a thousandth, or to a thousandth of their size for floats
+/// larger than one, so that conversions through `f32` units compare equal.
+pub fn assert_close(left: f32, right: f32) {
+    let tolerance = 1e-3 * right.abs().max(1.0);
+    assert!(
+        (left - right).abs() < tolerance,
+        "{} is not {}",
+        left,
+        right
+    );
+}